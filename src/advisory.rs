@@ -0,0 +1,381 @@
+use log::{error, info};
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read as _};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::crates_file::cargo_home;
+
+/// A known vulnerability affecting an installed crate or one of the dependencies built into it
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub id: String,
+    /// The affected package, which is a dependency when it differs from the crate name
+    pub package: String,
+    pub version: Version,
+    pub title: String,
+    pub patched: Vec<String>,
+}
+
+impl Advisory {
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let patched = if self.patched.is_empty() {
+            String::from("no patched version")
+        } else {
+            format!("patched {}", self.patched.join(", "))
+        };
+
+        format!(
+            "{} in {} v{}: {} ({patched})",
+            self.id, self.package, self.version, self.title
+        )
+    }
+}
+
+/// An installed crate to check along with its binaries
+#[derive(Debug, Clone)]
+pub struct ScanTarget {
+    pub name: String,
+    pub version: Version,
+    pub bins: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: AdvisoryVersions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    /// Set for notices such as unmaintained crates, which are not vulnerabilities
+    informational: Option<String>,
+    withdrawn: Option<toml::Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct AdvisoryVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+struct ParsedAdvisory {
+    id: String,
+    title: String,
+    patched: Vec<String>,
+    safe: Vec<VersionReq>,
+}
+
+impl ParsedAdvisory {
+    fn affects(&self, version: &Version) -> bool {
+        !self.safe.iter().any(|req| matches_prerelease(req, version))
+    }
+}
+
+/// `VersionReq::matches` with prereleases opted in the way cargo does it. A prerelease is
+/// compared by precedence, so `1.2.0-beta` is patched by `>= 1.1.0` but not by `< 1.2.0`.
+fn matches_prerelease(req: &VersionReq, version: &Version) -> bool {
+    if version.pre.is_empty() {
+        return req.matches(version);
+    }
+
+    let version = Version {
+        build: semver::BuildMetadata::EMPTY,
+        ..version.clone()
+    };
+
+    req.comparators
+        .iter()
+        .all(|comparator| comparator_contains(comparator, &version))
+}
+
+/// The lowest version of a release, below all of its prereleases
+fn lowest(major: u64, minor: u64, patch: u64) -> Version {
+    Version {
+        pre: Prerelease::new("0").unwrap_or_default(),
+        ..Version::new(major, minor, patch)
+    }
+}
+
+fn comparator_contains(comparator: &Comparator, version: &Version) -> bool {
+    let major = comparator.major;
+    let minor = comparator.minor.unwrap_or(0);
+    let patch = comparator.patch.unwrap_or(0);
+
+    let base = Version {
+        pre: comparator.pre.clone(),
+        ..Version::new(major, minor, patch)
+    };
+
+    // Where the versions a partial requirement like 1.2 stands for end
+    let partial_end = match (comparator.minor, comparator.patch) {
+        (None, _) => lowest(major + 1, 0, 0),
+        (Some(_), None) => lowest(major, minor + 1, 0),
+        (Some(_), Some(_)) => lowest(major, minor, patch + 1),
+    };
+
+    match comparator.op {
+        Op::Exact | Op::Wildcard if comparator.patch.is_some() => *version == base,
+        Op::Exact | Op::Wildcard => {
+            lowest(major, minor, patch) <= *version && *version < partial_end
+        }
+        Op::Greater if comparator.patch.is_some() => *version > base,
+        Op::Greater => *version >= partial_end,
+        Op::GreaterEq => *version >= base,
+        Op::Less if comparator.pre.is_empty() => *version < lowest(major, minor, patch),
+        Op::Less => *version < base,
+        Op::LessEq if comparator.patch.is_some() => *version <= base,
+        Op::LessEq => *version < partial_end,
+        Op::Tilde => {
+            let end = match comparator.minor {
+                Some(_) => lowest(major, minor + 1, 0),
+                None => lowest(major + 1, 0, 0),
+            };
+
+            base <= *version && *version < end
+        }
+        Op::Caret => {
+            let end = match (major, comparator.minor, comparator.patch) {
+                (0, None, _) => lowest(1, 0, 0),
+                (0, Some(0), None) => lowest(0, 1, 0),
+                (0, Some(0), Some(_)) => lowest(0, 0, patch + 1),
+                (0, Some(_), _) => lowest(0, minor + 1, 0),
+                _ => lowest(major + 1, 0, 0),
+            };
+
+            base <= *version && *version < end
+        }
+        _ => false,
+    }
+}
+
+/// Where cargo-audit keeps its clone of the advisory database
+#[must_use]
+pub fn default_db_path() -> Option<PathBuf> {
+    let mut location = cargo_home()?;
+    location.push("advisory-db");
+
+    Some(location)
+}
+
+/// Read the TOML front matter and the title of an advisory file
+fn parse_advisory(content: &str) -> Option<ParsedAdvisory> {
+    let start = content.find("```toml")? + "```toml".len();
+    let end = start + content[start..].find("```")?;
+
+    let file: AdvisoryFile = match toml::from_str(&content[start..end]) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed to parse advisory: {e}");
+            return None;
+        }
+    };
+
+    if file.advisory.informational.is_some() || file.advisory.withdrawn.is_some() {
+        return None;
+    }
+
+    let title = content[end + 3..]
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .unwrap_or("No title")
+        .trim()
+        .to_string();
+
+    let safe = file
+        .versions
+        .patched
+        .iter()
+        .chain(&file.versions.unaffected)
+        .filter_map(|req| VersionReq::parse(req).ok())
+        .collect();
+
+    Some(ParsedAdvisory {
+        id: file.advisory.id,
+        title,
+        patched: file.versions.patched,
+        safe,
+    })
+}
+
+fn read_advisories(db: &Path, package: &str) -> Vec<ParsedAdvisory> {
+    let Ok(entries) = fs::read_dir(db.join("crates").join(package)) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|entry| {
+            let mut content = String::new();
+            File::open(entry.path())
+                .ok()?
+                .read_to_string(&mut content)
+                .ok()?;
+
+            parse_advisory(&content)
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct AuditInfo {
+    packages: Vec<AuditPackage>,
+}
+
+#[derive(Deserialize)]
+struct AuditPackage {
+    name: String,
+    version: String,
+}
+
+/// The dependency list cargo-auditable embedded in a binary, read with rust-audit-info. Fails
+/// when rust-audit-info could not be started.
+async fn embedded_packages(bin: &Path) -> io::Result<Option<Vec<(String, Version)>>> {
+    let output = Command::new("rust-audit-info")
+        .arg(bin)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await?;
+
+    // Binaries built without cargo-auditable have nothing to read
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let Ok(info) = serde_json::from_slice::<AuditInfo>(&output.stdout) else {
+        return Ok(None);
+    };
+
+    Ok(Some(
+        info.packages
+            .into_iter()
+            .filter_map(|package| Some((package.name, Version::parse(&package.version).ok()?)))
+            .collect(),
+    ))
+}
+
+/// Match every crate, and the dependencies embedded in its binaries when available, against
+/// the advisory database
+pub async fn scan_advisories(
+    db: &Path,
+    targets: Vec<ScanTarget>,
+) -> Result<HashMap<String, Vec<Advisory>>, String> {
+    if !db.join("crates").is_dir() {
+        return Err(format!("No advisory database found at {}", db.display()));
+    }
+
+    let mut known: HashMap<String, Vec<ParsedAdvisory>> = HashMap::new();
+    let mut results = HashMap::new();
+    let mut dependency_scan = true;
+
+    for target in targets {
+        let mut packages = vec![(target.name.clone(), target.version.clone())];
+
+        for bin in &target.bins {
+            if !dependency_scan {
+                break;
+            }
+
+            match embedded_packages(bin).await {
+                Ok(embedded) => packages.extend(embedded.unwrap_or_default()),
+                Err(e) => {
+                    error!("Dependency scan unavailable, failed to run rust-audit-info: {e}");
+                    dependency_scan = false;
+                }
+            }
+        }
+
+        let mut found: Vec<Advisory> = Vec::new();
+
+        for (package, version) in packages {
+            let advisories = known
+                .entry(package.clone())
+                .or_insert_with(|| read_advisories(db, &package));
+
+            for advisory in advisories.iter().filter(|item| item.affects(&version)) {
+                if found.iter().any(|item| item.id == advisory.id) {
+                    continue;
+                }
+
+                found.push(Advisory {
+                    id: advisory.id.clone(),
+                    package: package.clone(),
+                    version: version.clone(),
+                    title: advisory.title.clone(),
+                    patched: advisory.patched.clone(),
+                });
+            }
+        }
+
+        if !found.is_empty() {
+            info!("{} has {} known advisories", target.name, found.len());
+            results.insert(target.name, found);
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(req: &str, version: &str) -> bool {
+        matches_prerelease(
+            &VersionReq::parse(req).unwrap(),
+            &Version::parse(version).unwrap(),
+        )
+    }
+
+    #[test]
+    fn releases_match_like_cargo() {
+        assert!(matches(">= 1.1.0", "1.2.3"));
+        assert!(!matches(">= 1.1.0", "1.0.9"));
+        assert!(matches("^0.3.2", "0.3.9"));
+        assert!(!matches("^0.3.2", "0.4.0"));
+    }
+
+    #[test]
+    fn prereleases_compare_by_precedence() {
+        assert!(matches(">= 1.1.0", "1.2.0-beta.1"));
+        assert!(!matches(">= 1.2.0", "1.2.0-beta.1"));
+        assert!(matches(">= 1.2.0-alpha", "1.2.0-beta.1"));
+        assert!(!matches("< 1.2.0", "1.2.0-beta.1"));
+        assert!(matches("< 1.2.0", "1.1.5-rc.1"));
+        assert!(matches("^1.1", "1.3.0-rc.1"));
+        assert!(!matches("^1.1", "2.0.0-rc.1"));
+        assert!(matches("~1.1.2", "1.1.3-alpha"));
+        assert!(!matches("~1.1.2", "1.2.0-alpha"));
+        assert!(matches("= 1.2.0-beta.1", "1.2.0-beta.1+build"));
+        assert!(matches(">= 1.1.0, < 1.2.0", "1.1.9-rc.2"));
+    }
+
+    #[test]
+    fn unaffected_ranges_keep_an_advisory_away() {
+        let advisory = ParsedAdvisory {
+            id: String::from("RUSTSEC-0000-0000"),
+            title: String::new(),
+            patched: vec![String::from(">= 2.0.0")],
+            safe: vec![
+                VersionReq::parse(">= 2.0.0").unwrap(),
+                VersionReq::parse("< 1.0.0").unwrap(),
+            ],
+        };
+
+        assert!(advisory.affects(&Version::parse("1.5.0").unwrap()));
+        assert!(advisory.affects(&Version::parse("2.0.0-rc.1").unwrap()));
+        assert!(!advisory.affects(&Version::parse("2.1.0-alpha").unwrap()));
+        assert!(!advisory.affects(&Version::parse("0.9.0-beta").unwrap()));
+    }
+}
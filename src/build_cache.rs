@@ -0,0 +1,113 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::crates_file::cargo_home;
+
+/// The target directory every install shares when the build cache is enabled, so common
+/// dependencies are only compiled once
+#[must_use]
+pub fn shared_target_dir() -> Option<PathBuf> {
+    let mut location = dirs::data_local_dir()?;
+    location.push("crane");
+    location.push("target");
+
+    Some(location)
+}
+
+/// Where sccache is installed, when it is on the `PATH`
+#[must_use]
+pub fn sccache_path() -> Option<PathBuf> {
+    let name = if cfg!(windows) {
+        "sccache.exe"
+    } else {
+        "sccache"
+    };
+
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Whether cargo already wraps rustc through the environment or `build.rustc-wrapper` in the
+/// cargo home config, which a `RUSTC_WRAPPER` set for the build would override
+#[must_use]
+pub fn rustc_wrapper_configured() -> bool {
+    if ["RUSTC_WRAPPER", "CARGO_BUILD_RUSTC_WRAPPER"]
+        .iter()
+        .any(|key| env::var_os(key).is_some())
+    {
+        return true;
+    }
+
+    let Some(home) = cargo_home() else {
+        return false;
+    };
+
+    ["config.toml", "config"].iter().any(|name| {
+        fs::read_to_string(home.join(name))
+            .ok()
+            .and_then(|content| toml::from_str::<toml::Table>(&content).ok())
+            .is_some_and(|config| {
+                config
+                    .get("build")
+                    .and_then(|build| build.get("rustc-wrapper"))
+                    .is_some()
+            })
+    })
+}
+
+/// The size of every file below a directory in bytes
+#[must_use]
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => entry.metadata().map_or(0, |m| m.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Remove the shared target directory, returning how many bytes were freed
+pub fn clean_shared_target() -> Result<u64, String> {
+    let Some(location) = shared_target_dir() else {
+        return Err(String::from("Failed to find the data directory"));
+    };
+
+    if !location.exists() {
+        return Ok(0);
+    }
+
+    let size = dir_size(&location);
+
+    fs::remove_dir_all(&location)
+        .map_err(|e| format!("Failed to remove {}: {e}", location.display()))?;
+
+    Ok(size)
+}
+
+/// Show a size in bytes as `512 B`, `3.4 MB` or `1.2 GB`
+#[must_use]
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
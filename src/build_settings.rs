@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Environment, toolchain and extra cargo arguments an install is built with
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildSettings {
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Passed to rustup as `cargo +toolchain`
+    #[serde(default)]
+    pub toolchain: Option<String>,
+    /// Appended to the install command
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+impl BuildSettings {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty() && self.toolchain.is_none() && self.extra_args.is_empty()
+    }
+
+    /// The crate settings on top of the global defaults. Variables and the toolchain of the
+    /// crate win, the extra arguments of both are kept.
+    #[must_use]
+    pub fn merged(&self, defaults: &BuildSettings) -> BuildSettings {
+        let mut env = defaults.env.clone();
+        env.extend(self.env.clone());
+
+        let extra_args = defaults
+            .extra_args
+            .iter()
+            .chain(&self.extra_args)
+            .cloned()
+            .collect();
+
+        BuildSettings {
+            env,
+            toolchain: self
+                .toolchain
+                .clone()
+                .or_else(|| defaults.toolchain.clone()),
+            extra_args,
+        }
+    }
+
+    /// The variables as `KEY=value` words, the way a shell takes them before a command
+    #[must_use]
+    pub fn env_words(&self) -> Vec<String> {
+        self.env
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect()
+    }
+}
+
+/// Whether a word is a `KEY=value` assignment rather than the program to run
+fn split_assignment(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once('=')?;
+
+    let valid = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    valid.then_some((key, value))
+}
+
+/// Whether a variable looks like it holds a credential, going by its name
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+
+    [
+        "TOKEN",
+        "SECRET",
+        "PASSWORD",
+        "PASSWD",
+        "CREDENTIAL",
+        "API_KEY",
+        "PRIVATE_KEY",
+    ]
+    .iter()
+    .any(|part| key.contains(part))
+}
+
+/// The command with the values of credential variables hidden, for the log and the history
+#[must_use]
+pub fn mask_secrets(command: &[String]) -> Vec<String> {
+    let (variables, _) = split_env(command);
+
+    command
+        .iter()
+        .enumerate()
+        .map(|(index, word)| match split_assignment(word) {
+            Some((key, _)) if index < variables.len() && is_secret(key) => format!("{key}=***"),
+            _ => word.clone(),
+        })
+        .collect()
+}
+
+/// Read `RUSTFLAGS="-C target-cpu=native" CC=clang` into variables
+pub fn parse_env(input: &str) -> Result<BTreeMap<String, String>, String> {
+    split_words(input)?
+        .iter()
+        .map(|word| {
+            split_assignment(word)
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| format!("{word} is not a KEY=value variable"))
+        })
+        .collect()
+}
+
+/// Split a command into the leading variable assignments and the command itself
+#[must_use]
+pub fn split_env(command: &[String]) -> (Vec<(&str, &str)>, &[String]) {
+    let count = command
+        .iter()
+        .take_while(|word| split_assignment(word).is_some())
+        .count();
+
+    let (assignments, rest) = command.split_at(count);
+
+    (
+        assignments
+            .iter()
+            .filter_map(|word| split_assignment(word))
+            .collect(),
+        rest,
+    )
+}
+
+/// Split a line into words like a shell would, honouring quotes and backslashes
+pub fn split_words(input: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"') | None, '\\') => {
+                let Some(escaped) = chars.next() else {
+                    return Err(String::from("Nothing follows the last backslash"));
+                };
+
+                word.push(escaped);
+                in_word = true;
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if let Some(open) = quote {
+        return Err(format!("Missing closing {open}"));
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Quote a word so `split_words` reads it back unchanged
+#[must_use]
+pub fn quote_word(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | '=' | '+' | ',' | '@' | '~')
+        });
+
+    if plain {
+        return word.to_string();
+    }
+
+    // Keep the assignment readable as KEY='some value'
+    if let Some((key, value)) = split_assignment(word) {
+        return format!("{key}={}", quote_word(value));
+    }
+
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Show a command the way it could be typed into a shell
+#[must_use]
+pub fn join_words(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| quote_word(word))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn split_words_honours_quotes_and_backslashes() {
+        assert_eq!(
+            split_words(r#"RUSTFLAGS="-C target-cpu=native" cargo  install 'my tool'"#),
+            Ok(words(&[
+                "RUSTFLAGS=-C target-cpu=native",
+                "cargo",
+                "install",
+                "my tool"
+            ]))
+        );
+        assert_eq!(
+            split_words(r#"a\ b "c \"d\"" 'e\f' '' """#),
+            Ok(words(&["a b", r#"c "d""#, r"e\f", "", ""]))
+        );
+        assert_eq!(split_words("  "), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn split_words_rejects_unfinished_input() {
+        assert_eq!(
+            split_words("cargo 'install"),
+            Err(String::from("Missing closing '"))
+        );
+        assert_eq!(
+            split_words(r#"cargo "install"#),
+            Err(String::from("Missing closing \""))
+        );
+        assert_eq!(
+            split_words(r"cargo \"),
+            Err(String::from("Nothing follows the last backslash"))
+        );
+    }
+
+    #[test]
+    fn join_words_reads_back() {
+        let commands = [
+            words(&["cargo", "install", "ripgrep", "--features", "pcre2"]),
+            words(&["RUSTFLAGS=-C target-cpu=native", "cargo", "install", "x"]),
+            words(&["it's", "a \"quoted\" word", "back\\slash", "", "$HOME", "*"]),
+            words(&["CC=", "--config", "profile.release.lto=true"]),
+        ];
+
+        for command in commands {
+            assert_eq!(split_words(&join_words(&command)), Ok(command));
+        }
+    }
+
+    #[test]
+    fn quote_word_keeps_assignments_readable() {
+        assert_eq!(quote_word("--locked"), "--locked");
+        assert_eq!(quote_word("RUSTFLAGS=-C opt"), "RUSTFLAGS='-C opt'");
+        assert_eq!(quote_word("it's"), r"'it'\''s'");
+        assert_eq!(quote_word(""), "''");
+    }
+
+    #[test]
+    fn split_env_takes_leading_assignments() {
+        let command = words(&["CC=clang", "A_1=x=y", "cargo", "install", "B=2"]);
+        let (variables, rest) = split_env(&command);
+
+        assert_eq!(variables, [("CC", "clang"), ("A_1", "x=y")]);
+        assert_eq!(rest, &command[2..]);
+    }
+
+    #[test]
+    fn parse_env_needs_assignments() {
+        assert_eq!(
+            parse_env(r#"RUSTFLAGS="-C target-cpu=native" CC=clang"#),
+            Ok(BTreeMap::from([
+                (String::from("CC"), String::from("clang")),
+                (
+                    String::from("RUSTFLAGS"),
+                    String::from("-C target-cpu=native")
+                ),
+            ]))
+        );
+        assert!(parse_env("1A=b").is_err());
+        assert!(parse_env("cargo").is_err());
+    }
+
+    #[test]
+    fn mask_secrets_hides_leading_credentials() {
+        let command = words(&[
+            "GITHUB_TOKEN=abc",
+            "db_password=hunter2",
+            "CARGO_REGISTRIES_MY_API_KEY=k",
+            "RUSTFLAGS=-C opt",
+            "cargo",
+            "install",
+            "tool",
+            "--config",
+            "NPM_TOKEN=shown",
+        ]);
+
+        assert_eq!(
+            mask_secrets(&command),
+            words(&[
+                "GITHUB_TOKEN=***",
+                "db_password=***",
+                "CARGO_REGISTRIES_MY_API_KEY=***",
+                "RUSTFLAGS=-C opt",
+                "cargo",
+                "install",
+                "tool",
+                "--config",
+                "NPM_TOKEN=shown",
+            ])
+        );
+    }
+
+    #[test]
+    fn merged_prefers_crate_settings() {
+        let defaults = BuildSettings {
+            env: BTreeMap::from([
+                (String::from("CC"), String::from("gcc")),
+                (String::from("A"), String::from("1")),
+            ]),
+            toolchain: Some(String::from("stable")),
+            extra_args: words(&["--locked"]),
+        };
+        let settings = BuildSettings {
+            env: BTreeMap::from([(String::from("CC"), String::from("clang"))]),
+            toolchain: None,
+            extra_args: words(&["--offline"]),
+        };
+
+        let merged = settings.merged(&defaults);
+
+        assert_eq!(merged.env_words(), words(&["A=1", "CC=clang"]));
+        assert_eq!(merged.toolchain.as_deref(), Some("stable"));
+        assert_eq!(merged.extra_args, words(&["--locked", "--offline"]));
+    }
+}
@@ -0,0 +1,565 @@
+use iced::futures::channel::mpsc::Sender;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::pin::Pin;
+
+use crate::LocalCrate;
+use crate::config::Config;
+use crate::crates_file::{parse_local_crates, read_crates_file};
+use crate::discover::{fetch_release_bins, user_agent};
+use crate::index::latest_release;
+use crate::toolchain::rustc_version;
+use crate::worker::{WorkerEvent, WorkerInput, event_worker};
+
+/// Exit code when a lookup or a cargo command failed
+const EXIT_FAILURE: i32 = 1;
+
+/// Exit code of check when at least one crate can be updated
+const EXIT_UPDATES_PENDING: i32 = 2;
+
+const USAGE: &str = "Usage: crane [COMMAND]
+
+Without a command the window is opened.
+
+Commands:
+  list [--json]              List the installed crates
+  check [--json]             Check for updates, exits with 2 when any are pending
+  update --all               Update every crate that is not pinned or has a yanked version
+  update <NAME>...           Update the given crates
+  remove <NAME>...           Uninstall the given crates
+  help                       Show this message
+
+Exits with 1 when a lookup or a cargo command fails.";
+
+enum Command {
+    List { json: bool },
+    Check { json: bool },
+    Update { all: bool, names: Vec<String> },
+    Remove { names: Vec<String> },
+    Help,
+}
+
+/// A crate as printed by list and check
+#[derive(Serialize)]
+struct CrateStatus<'a> {
+    name: &'a str,
+    version: String,
+    latest_version: Option<String>,
+    newest_version: Option<String>,
+    policy: String,
+    follow_prereleases: bool,
+    git: Option<&'a str>,
+    git_ref: Option<String>,
+    local_hash: Option<&'a str>,
+    latest_hash: Option<&'a str>,
+    pinned: bool,
+    yanked: bool,
+    update_available: bool,
+}
+
+impl<'a> CrateStatus<'a> {
+    fn new(item: &'a LocalCrate) -> Self {
+        Self {
+            name: &item.name,
+            version: item.version.to_string(),
+            latest_version: item.crates_version.as_ref().map(ToString::to_string),
+            newest_version: item.newest_version.as_ref().map(ToString::to_string),
+            policy: item.policy.to_string(),
+            follow_prereleases: item.follow_prereleases,
+            git: item.git_link.as_deref(),
+            git_ref: item.git_link.as_ref().map(|_| item.git_ref.to_string()),
+            local_hash: item.local_hash.as_deref(),
+            latest_hash: item.latest_hash.as_deref(),
+            pinned: item.pinned,
+            yanked: item.is_yanked(),
+            update_available: item.has_update() || item.yanked_update(),
+        }
+    }
+}
+
+/// The background worker of the app, driven without a window
+struct Headless {
+    worker: Sender<WorkerInput>,
+    events: Pin<Box<dyn Stream<Item = WorkerEvent>>>,
+}
+
+impl Headless {
+    async fn start() -> Option<Self> {
+        let mut events: Pin<Box<dyn Stream<Item = WorkerEvent>>> = Box::pin(event_worker());
+
+        match events.next().await {
+            Some(WorkerEvent::Ready(worker)) => Some(Self { worker, events }),
+            _ => None,
+        }
+    }
+
+    async fn send(&mut self, input: WorkerInput) {
+        let _ = self.worker.send(input).await;
+    }
+
+    async fn next(&mut self) -> Option<WorkerEvent> {
+        self.events.next().await
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(Command::Help);
+    };
+
+    let json = rest.iter().any(|arg| arg == "--json");
+    let all = rest.iter().any(|arg| arg == "--all");
+
+    let names = rest
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    let allowed_flags: &[&str] = match command.as_str() {
+        "list" | "check" => &["--json"],
+        "update" => &["--all"],
+        _ => &[],
+    };
+
+    if let Some(flag) = rest
+        .iter()
+        .find(|arg| arg.starts_with("--") && !allowed_flags.contains(&arg.as_str()))
+    {
+        return Err(format!("Unknown option {flag} for {command}"));
+    }
+
+    match command.as_str() {
+        "list" | "check" if !names.is_empty() => {
+            Err(format!("{command} does not take crate names"))
+        }
+        "list" => Ok(Command::List { json }),
+        "check" => Ok(Command::Check { json }),
+        "update" if all != names.is_empty() => {
+            Err(String::from("update needs either --all or crate names"))
+        }
+        "update" => Ok(Command::Update { all, names }),
+        "remove" if names.is_empty() => Err(String::from("remove needs crate names")),
+        "remove" => Ok(Command::Remove { names }),
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("Unknown command {command}")),
+    }
+}
+
+/// Run a command without opening the window and return the exit code
+pub fn run(args: &[String]) -> i32 {
+    let command = match parse_args(args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return EXIT_FAILURE;
+        }
+    };
+
+    if let Command::Help = command {
+        println!("{USAGE}");
+        return 0;
+    }
+
+    let crate_file = match read_crates_file() {
+        Ok(crate_file) => crate_file,
+        Err(e) => {
+            eprintln!("{e}");
+            return EXIT_FAILURE;
+        }
+    };
+
+    let mut config = Config::get_or_new();
+    let mut crate_list = parse_local_crates(crate_file, config.as_ref());
+
+    if let Command::List { json } = command {
+        print_crates(crate_list.values(), json);
+        return 0;
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start the runtime: {e}");
+            return EXIT_FAILURE;
+        }
+    };
+
+    runtime.block_on(async {
+        let Some(mut headless) = Headless::start().await else {
+            eprintln!("Failed to start the worker");
+            return EXIT_FAILURE;
+        };
+
+        match command {
+            Command::Check { json } => {
+                check(&mut headless, &mut crate_list, &mut config, json).await
+            }
+            Command::Update { all, names } => {
+                update(&mut headless, &mut crate_list, &mut config, all, &names).await
+            }
+            Command::Remove { names } => remove(&mut headless, &crate_list, names).await,
+            Command::List { .. } | Command::Help => 0,
+        }
+    })
+}
+
+fn print_crates<'a>(crates: impl Iterator<Item = &'a LocalCrate>, json: bool) {
+    if json {
+        let statuses = crates.map(CrateStatus::new).collect::<Vec<CrateStatus>>();
+
+        match serde_json::to_string_pretty(&statuses) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("Failed to serialize crates: {e}"),
+        }
+
+        return;
+    }
+
+    for item in crates {
+        let mut line = format!("{} v{}", item.name, item.version);
+
+        if let Some(git_link) = &item.git_link {
+            line.push_str(&format!(" ({git_link}, {})", item.git_ref));
+        }
+
+        if item.has_update() || item.yanked_update() {
+            if item.git_link.is_some() {
+                let short_local = item.local_hash.as_deref().unwrap_or("unknown");
+                let short_latest = item.latest_hash.as_deref().unwrap_or_default();
+
+                line.push_str(&format!(
+                    " {} → {}",
+                    &short_local[..short_local.len().min(7)],
+                    &short_latest[..short_latest.len().min(7)]
+                ));
+            } else if let Some(version) = &item.crates_version {
+                line.push_str(&format!(" → v{version}"));
+            }
+        }
+
+        if item.pinned {
+            line.push_str(" [pinned]");
+        }
+
+        if item.is_yanked() {
+            line.push_str(" [yanked]");
+        }
+
+        if let Some(newest) = item.outside_policy() {
+            line.push_str(&format!(" [v{newest} held back by {} policy]", item.policy));
+        }
+
+        println!("{line}");
+    }
+}
+
+/// Look up the latest version or commit of every crate, returning the ones that failed
+async fn fetch_latest(
+    headless: &mut Headless,
+    crate_list: &mut BTreeMap<String, LocalCrate>,
+    config: &mut Option<Config>,
+) -> Vec<String> {
+    let mut crate_names = Vec::new();
+    let mut git_crates = HashMap::new();
+
+    for item in crate_list.values() {
+        if let Some(git_source) = item.git_source() {
+            git_crates.insert(item.name.clone(), git_source);
+        } else {
+            crate_names.push(item.name.clone());
+        }
+    }
+
+    // Fall back to releases the toolchain of each crate can build, as the window does
+    if let Some(config) = config.as_ref().filter(|c| c.msrv_fallback) {
+        let mut toolchains = HashMap::new();
+
+        for item in crate_list.values_mut() {
+            let toolchain = item.build.merged(&config.build_defaults).toolchain;
+
+            if !toolchains.contains_key(&toolchain) {
+                let version = rustc_version(toolchain.as_deref())
+                    .await
+                    .ok()
+                    .map(|rustc| rustc.version);
+
+                toolchains.insert(toolchain.clone(), version);
+            }
+
+            item.msrv_limit.clone_from(&toolchains[&toolchain]);
+        }
+    }
+
+    let mut failed = Vec::new();
+
+    if !crate_names.is_empty() {
+        headless
+            .send(WorkerInput::crate_version(crate_names, config.as_ref()))
+            .await;
+
+        while let Some(event) = headless.next().await {
+            match event {
+                WorkerEvent::SuccessIndex {
+                    crate_name,
+                    versions,
+                } => {
+                    let Some(target_crate) = crate_list.get_mut(&crate_name) else {
+                        continue;
+                    };
+
+                    if latest_release(&versions, true).is_none() {
+                        failed.push(crate_name);
+                        continue;
+                    }
+
+                    target_crate.set_versions(versions);
+
+                    if let Some(config) = config
+                        && let Some(target) = &target_crate.crates_version
+                    {
+                        config.update_cache(
+                            crate_name,
+                            target_crate.available_features().clone(),
+                            target.to_string(),
+                        );
+                    }
+                }
+                WorkerEvent::ErrorCrate(crate_name) => failed.push(crate_name),
+                WorkerEvent::DoneCrateCheck => break,
+                _ => {}
+            }
+        }
+    }
+
+    let mut remaining = git_crates.len();
+
+    if remaining > 0 {
+        headless
+            .send(WorkerInput::GetGitCommit(git_crates, false))
+            .await;
+    }
+
+    while remaining > 0 {
+        let Some(event) = headless.next().await else {
+            break;
+        };
+
+        match event {
+            WorkerEvent::SuccessGitCommit {
+                crate_name, commit, ..
+            } => {
+                if let Some(target_crate) = crate_list.get_mut(&crate_name) {
+                    target_crate.set_latest_commit(commit);
+                }
+
+                remaining -= 1;
+            }
+            WorkerEvent::ErrorGitCommit { crate_name, .. } => {
+                failed.push(crate_name);
+                remaining -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    failed.sort();
+    failed
+}
+
+async fn check(
+    headless: &mut Headless,
+    crate_list: &mut BTreeMap<String, LocalCrate>,
+    config: &mut Option<Config>,
+    json: bool,
+) -> i32 {
+    let failed = fetch_latest(headless, crate_list, config).await;
+
+    let pending = crate_list
+        .values()
+        .filter(|item| item.should_update())
+        .count();
+
+    if json {
+        print_crates(crate_list.values(), true);
+    } else if pending == 0 {
+        println!("All crates are up to date");
+    } else {
+        print_crates(
+            crate_list.values().filter(|item| item.should_update()),
+            false,
+        );
+    }
+
+    if !failed.is_empty() {
+        eprintln!("Failed to check {}", failed.join(", "));
+        return EXIT_FAILURE;
+    }
+
+    if pending > 0 { EXIT_UPDATES_PENDING } else { 0 }
+}
+
+async fn update(
+    headless: &mut Headless,
+    crate_list: &mut BTreeMap<String, LocalCrate>,
+    config: &mut Option<Config>,
+    all: bool,
+    names: &[String],
+) -> i32 {
+    if !all && let Some(unknown) = names.iter().find(|name| !crate_list.contains_key(*name)) {
+        eprintln!("{unknown} is not installed");
+        return EXIT_FAILURE;
+    }
+
+    // Check the named crates first so the policy, fallback and yanked rules apply to current
+    // releases rather than the cached ones
+    let mut failed = if all {
+        fetch_latest(headless, crate_list, config).await
+    } else {
+        let mut named = names
+            .iter()
+            .filter_map(|name| crate_list.remove_entry(name))
+            .collect::<BTreeMap<String, LocalCrate>>();
+
+        let failed = fetch_latest(headless, &mut named, config).await;
+        crate_list.extend(named);
+
+        failed
+    };
+
+    if !failed.is_empty() {
+        eprintln!("Failed to check {}", failed.join(", "));
+    }
+
+    let mut targets = if all {
+        crate_list
+            .values()
+            .filter(|item| item.should_update())
+            .cloned()
+            .collect::<Vec<LocalCrate>>()
+    } else {
+        names
+            .iter()
+            .filter_map(|name| crate_list.get(name))
+            .filter(|item| !failed.contains(&item.name))
+            .filter(|item| {
+                if item.should_update() {
+                    return true;
+                }
+
+                if item.pinned {
+                    println!("{} is pinned, skipping", item.name);
+                } else {
+                    println!("{} is up to date", item.name);
+                }
+
+                false
+            })
+            .cloned()
+            .collect()
+    };
+
+    if targets.is_empty() {
+        println!("Nothing to update");
+        return if failed.is_empty() { 0 } else { EXIT_FAILURE };
+    }
+
+    let rate_limit = config.as_ref().map_or(1000, |c| c.crate_rate_limit_ms);
+
+    // A --bin subset from the original install can only be told apart from every binary of
+    // the release once the release binaries are known
+    let releases = targets
+        .iter()
+        .filter_map(|item| Some((item.name.clone(), item.bins_release()?.clone())))
+        .collect();
+
+    let results = match Client::builder().user_agent(user_agent()).build() {
+        Ok(client) => fetch_release_bins(&client, releases, rate_limit).await,
+        Err(e) => {
+            eprintln!("Failed to create client: {e}");
+            Vec::new()
+        }
+    };
+
+    for (crate_name, version, result) in results {
+        match result {
+            Ok(bins) => {
+                if let Some(target) = targets.iter_mut().find(|item| item.name == crate_name) {
+                    target.release_bins = Some((version, bins));
+                }
+            }
+            Err(e) => eprintln!("Failed to fetch the binaries of {crate_name}: {e}"),
+        }
+    }
+
+    let defaults = config
+        .as_ref()
+        .map(Config::install_defaults)
+        .unwrap_or_default();
+
+    // Builds running side by side need their lines told apart
+    let attribute = defaults.parallel > 1;
+
+    headless
+        .send(WorkerInput::UpdateCrates(targets, defaults))
+        .await;
+
+    failed.extend(run_operation(headless, attribute).await);
+
+    if failed.is_empty() { 0 } else { EXIT_FAILURE }
+}
+
+async fn remove(
+    headless: &mut Headless,
+    crate_list: &BTreeMap<String, LocalCrate>,
+    names: Vec<String>,
+) -> i32 {
+    if let Some(unknown) = names.iter().find(|name| !crate_list.contains_key(*name)) {
+        eprintln!("{unknown} is not installed");
+        return EXIT_FAILURE;
+    }
+
+    let targets = names
+        .iter()
+        .filter_map(|name| crate_list.get(name))
+        .cloned()
+        .collect();
+
+    headless.send(WorkerInput::DeleteCrates(targets)).await;
+
+    if run_operation(headless, false).await.is_empty() {
+        0
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+/// Stream the cargo output of a running batch until it is done, returning the crates that
+/// failed. Lines are prefixed with their crate when builds run side by side.
+async fn run_operation(headless: &mut Headless, attribute: bool) -> Vec<String> {
+    let mut failed = Vec::new();
+
+    while let Some(event) = headless.next().await {
+        match event {
+            WorkerEvent::CrateLog(crate_name, line) if attribute => {
+                eprintln!("{crate_name}: {line}");
+            }
+            WorkerEvent::CrateLog(_, line) => eprintln!("{line}"),
+            WorkerEvent::CrateResult {
+                crate_name, result, ..
+            } => {
+                println!("{crate_name}: {}", result.summary());
+
+                if !result.is_success() {
+                    failed.push(crate_name);
+                }
+            }
+            WorkerEvent::DoneUpdate | WorkerEvent::DoneDelete => break,
+            _ => {}
+        }
+    }
+
+    failed
+}
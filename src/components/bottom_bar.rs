@@ -1,9 +1,13 @@
+use iced::widget::text::Wrapping;
 use iced::widget::{center, column, container, progress_bar, row, text};
 use iced::{Alignment, Color, Element, Length, Theme};
 
+use crate::history::format_duration;
 use crate::icon::right_arrow;
-use crate::utils::{bold, primary_button, secondary_button};
-use crate::{MainWindow, Message, OperationType, Page};
+use crate::lerp::LerpState;
+use crate::message::ReviewEvent;
+use crate::utils::{bold, danger_button, primary_button, secondary_button};
+use crate::{MainWindow, Message, OperationCrate, OperationType, Page};
 
 pub const OPERATION_CONTAINER: f64 = 60.0;
 pub const OPERATION_CONTAINER_KEY: &str = "operation_container_height";
@@ -14,6 +18,62 @@ pub const FETCH_PROGRESS_KEY: &str = "fetch_progress";
 
 pub const OPERATION_PROGRESS_KEY: &str = "operation_progress";
 
+pub const BUILD_PROGRESS_HEIGHT: f64 = 30.0;
+const BUILD_PROGRESS_KEY: &str = "build_progress";
+
+/// Each crate being built animates its own bar
+#[must_use]
+pub fn build_progress_key(crate_name: &str) -> String {
+    format!("{BUILD_PROGRESS_KEY}_{crate_name}")
+}
+
+/// A bar following cargo through the build of a crate being installed
+fn build_progress<'a>(working: &OperationCrate, lerp_state: &LerpState) -> Element<'a, Message> {
+    let build_text = match &working.build {
+        None => format!("{}: preparing build", working.name),
+        Some(build) => {
+            let mut parts = vec![match build.total {
+                Some(total) => format!("{}: {}/{total} units", working.name, build.compiled),
+                None => format!("{}: {} units", working.name, build.compiled),
+            }];
+
+            if !build.current.is_empty() {
+                parts.push(build.current.clone());
+            }
+
+            if let Some(remaining) = build.remaining(working.started.elapsed()) {
+                let remaining_ms = u64::try_from(remaining.as_millis()).unwrap_or(u64::MAX);
+                parts.push(format!("about {} left", format_duration(remaining_ms)));
+            }
+
+            parts.join(", ")
+        }
+    };
+
+    let progress_bar = container(
+        progress_bar(
+            0.0..=100.0,
+            lerp_state
+                .get(&build_progress_key(&working.name))
+                .unwrap_or_default() as f32,
+        )
+        .girth(5.0),
+    )
+    .width(Length::FillPortion(1))
+    .padding(5);
+
+    let build_text = text(build_text)
+        .size(12)
+        .wrapping(Wrapping::None)
+        .width(Length::FillPortion(2));
+
+    row![progress_bar, build_text]
+        .spacing(5)
+        .height(BUILD_PROGRESS_HEIGHT as f32)
+        .align_y(Alignment::Center)
+        .into()
+}
+
 impl MainWindow {
     #[must_use]
     pub fn fetch_progress(&self) -> Element<'_, Message> {
@@ -61,10 +121,24 @@ impl MainWindow {
     pub fn operation_prompt(&self) -> Element<'_, Message> {
         let total_operation = self.delete_crates.len() + self.update_crates.len();
 
-        let operation_text = if let Some(working) = self.operation_crate.as_ref() {
-            let operation_text = match working.operation_type {
-                OperationType::Update => format!("Updating {}", working.name),
-                OperationType::Delete => format!("Deleting {}", working.name),
+        let operation_text = if let Some(operation) = self.operation {
+            let names = self
+                .operation_crates
+                .iter()
+                .map(|working| working.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+
+            let operation_text = match operation {
+                OperationType::Update => format!(
+                    "Updating {names} ({} of {total_operation} done)",
+                    self.operation_done
+                ),
+                OperationType::Delete => format!(
+                    "Deleting {names} ({} of {total_operation} done)",
+                    self.operation_done
+                ),
+                OperationType::Rollback => format!("Rolling back {names}"),
             };
 
             text(operation_text).font(bold())
@@ -106,12 +180,12 @@ impl MainWindow {
             .align_y(Alignment::Center);
 
         if self.fetch_progress.is_none() {
-            apply_button = apply_button.on_press(Message::ApplyOperation);
+            apply_button = apply_button.on_press(Message::Review(ReviewEvent::Show));
         }
 
         let mut layout = column![center(operation_text)].width(Length::Fill);
 
-        if self.operation_crate.is_none() {
+        if self.operation.is_none() {
             let buttons = row![cancel_button, apply_button, log_button]
                 .spacing(10)
                 .align_y(Alignment::Center);
@@ -137,11 +211,29 @@ impl MainWindow {
 
             log_button = log_button.padding(5);
 
-            let container = row![progress_bar, log_button]
+            let stop_text = if self.stopping_operation {
+                "Stopping"
+            } else {
+                "Stop"
+            };
+
+            let mut stop_button = danger_button(text(stop_text).font(bold()));
+
+            if !self.stopping_operation {
+                stop_button = stop_button.on_press(Message::StopOperation);
+            }
+
+            let container = row![progress_bar, stop_button, log_button]
                 .spacing(5.0)
                 .align_y(Alignment::Center);
 
             layout = layout.push(container);
+
+            if self.operation == Some(OperationType::Update) {
+                for working in &self.operation_crates {
+                    layout = layout.push(build_progress(working, &self.lerp_state));
+                }
+            }
         }
 
         let container_height = self.lerp_state.get(OPERATION_CONTAINER_KEY).unwrap_or(0.0) as f32;
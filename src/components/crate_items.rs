@@ -4,10 +4,16 @@ use iced::widget::text::Wrapping;
 use iced::widget::tooltip::Position;
 use iced::widget::{center, column, container, mouse_area, row, scrollable, space, text, tooltip};
 use iced::{Alignment, Border, Color, Element, Length, Padding, Shadow, Theme};
-use std::collections::BTreeSet;
 
+use crate::advisory::Advisory;
+use crate::build_cache::format_size;
 use crate::icon::{github, lock, pin, refresh, tick, trash};
-use crate::utils::{bold, danger_button, primary_button, toggler_button, toggler_button_primary};
+use crate::message::{BuildEvent, ManifestEvent, PolicyEvent, VersionPickerEvent};
+use crate::policy::UpdatePolicy;
+use crate::utils::{
+    bold, danger_button, mono, primary_button, secondary_button, toggler_button,
+    toggler_button_primary,
+};
 use crate::{MainWindow, Message};
 
 impl MainWindow {
@@ -23,11 +29,173 @@ impl MainWindow {
             select_all_button = select_all_button.on_press(Message::UpdateAll);
         }
 
-        let button_container = container(select_all_button).padding(Padding {
-            bottom: 5.0,
-            ..Default::default()
+        let defaults = self.install_defaults();
+
+        let always_locked_button = tooltip(
+            toggler_button(text("Always --locked").font(bold()), defaults.locked)
+                .on_press(Message::ToggleAlwaysLocked),
+            "Use --locked for every crate without its own lock setting",
+            Position::Bottom,
+        )
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weaker.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        });
+
+        let msrv_fallback_button = tooltip(
+            toggler_button(
+                text("Compatible Only").font(bold()),
+                self.config.as_ref().is_some_and(|config| config.msrv_fallback),
+            )
+            .on_press(Message::ToggleMsrvFallback),
+            "Update to the newest release the active rustc can build instead of one needing a newer Rust",
+            Position::Bottom,
+        )
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weaker.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
         });
 
+        let shared_target = self
+            .config
+            .as_ref()
+            .is_some_and(|config| config.shared_target);
+
+        let mut cache_tooltip = String::from("Build every install in one target directory");
+
+        if self.sccache {
+            cache_tooltip.push_str(" with sccache");
+        }
+
+        if let Some(size) = self.build_cache_size {
+            cache_tooltip.push_str(&format!(", {} used", format_size(size)));
+        }
+
+        let shared_target_button = tooltip(
+            toggler_button(text("Shared Build Cache").font(bold()), shared_target)
+                .on_press(Message::ToggleSharedTarget),
+            text(cache_tooltip),
+            Position::Bottom,
+        )
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weaker.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        });
+
+        let manifest_button = secondary_button(text("Manifest").font(bold()))
+            .on_press(Message::Manifest(ManifestEvent::Show));
+
+        let history_button =
+            secondary_button(text("History").font(bold())).on_press(Message::ShowHistory);
+
+        let discover_button =
+            secondary_button(text("Discover").font(bold())).on_press(Message::ShowDiscover);
+
+        let mut header_buttons = row![
+            select_all_button,
+            always_locked_button,
+            msrv_fallback_button,
+            shared_target_button,
+            manifest_button,
+            history_button,
+            discover_button
+        ]
+        .spacing(5);
+
+        if self
+            .crate_list
+            .values()
+            .any(|item| !item.advisories.is_empty())
+        {
+            let fix_button = tooltip(
+                danger_button(text("Fix Vulnerable").font(bold())).on_press(Message::FixVulnerable),
+                "Select every crate with a known advisory for reinstall",
+                Position::Bottom,
+            )
+            .style(|theme: &Theme| {
+                let palette = theme.palette();
+                container::Style {
+                    background: Some(palette.background.weaker.color.into()),
+                    text_color: Some(palette.background.weak.text),
+                    border: Border {
+                        radius: 8.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            });
+
+            header_buttons = header_buttons.push(fix_button);
+        }
+
+        if self.crate_list.values().any(|item| {
+            self.rustc_for(item)
+                .is_some_and(|rustc| item.outdated_toolchain(rustc).is_some())
+        }) {
+            let rebuild_button = tooltip(
+                secondary_button(text("Rebuild Outdated Toolchain").font(bold()))
+                    .on_press(Message::RebuildOutdated),
+                "Select every crate built with another rustc for a reinstall of the same version",
+                Position::Bottom,
+            )
+            .style(|theme: &Theme| {
+                let palette = theme.palette();
+                container::Style {
+                    background: Some(palette.background.weaker.color.into()),
+                    text_color: Some(palette.background.weak.text),
+                    border: Border {
+                        radius: 8.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            });
+
+            header_buttons = header_buttons.push(rebuild_button);
+        }
+
+        if let Some(size) = self.build_cache_size.filter(|size| *size > 0) {
+            let mut clean_button =
+                secondary_button(text(format!("Clean Cache ({})", format_size(size))).font(bold()));
+
+            if self.operation.is_none() {
+                clean_button = clean_button.on_press(Message::CleanBuildCache);
+            }
+
+            header_buttons = header_buttons.push(clean_button);
+        }
+
+        // Let the buttons flow onto another line on narrow windows
+        let button_container =
+            container(header_buttons.wrap().vertical_spacing(5)).padding(Padding {
+                bottom: 5.0,
+                ..Default::default()
+            });
+
         let crate_columns = column![button_container];
 
         let crate_cards = self
@@ -35,16 +203,251 @@ impl MainWindow {
             .values()
             .enumerate()
             .fold(crate_columns, |column, (index, crate_item)| {
-                let details = column![
+                let mut details = column![
                     text(&crate_item.name).size(18).font(bold()),
                     text(&crate_item.description)
                         .size(15)
                         .wrapping(Wrapping::Glyph),
-                    space::horizontal(),
                 ]
                 .spacing(8)
                 .align_x(Alignment::Start);
 
+                // Show the install options that will be passed again on reinstall
+                let preserved_flags = crate_item.preserved_flags();
+
+                if !preserved_flags.is_empty() {
+                    details = details.push(
+                        text(format!("Preserved: {}", preserved_flags.join(" ")))
+                            .size(12)
+                            .font(mono())
+                            .wrapping(Wrapping::Glyph),
+                    );
+                }
+
+                if let Some(newest) = crate_item.outside_policy() {
+                    details = details.push(
+                        text(format!(
+                            "v{newest} is available outside the {} policy",
+                            crate_item.policy
+                        ))
+                        .size(12),
+                    );
+                }
+
+                if let Some(prerelease) = crate_item.newer_prerelease() {
+                    details = details
+                        .push(text(format!("Prerelease v{prerelease} is available")).size(12));
+                }
+
+                if let Some(rustc) = self.rustc_for(crate_item)
+                    && let Some(blocked) = crate_item.msrv_blocked(&rustc.version)
+                {
+                    let required = blocked.rust_version.as_deref().unwrap_or_default();
+
+                    let msrv_text = match crate_item
+                        .crates_version
+                        .as_ref()
+                        .filter(|target| *target != &blocked.version)
+                    {
+                        Some(target) if target > &crate_item.version => format!(
+                            "v{} needs Rust {required}, updating to v{target} instead",
+                            blocked.version
+                        ),
+                        Some(_) => format!(
+                            "v{} needs Rust {required} and no newer release supports rustc {rustc}",
+                            blocked.version
+                        ),
+                        None => format!(
+                            "v{} needs Rust {required}, newer than rustc {rustc}",
+                            blocked.version
+                        ),
+                    };
+
+                    details =
+                        details.push(text(msrv_text).size(12).style(|theme: &Theme| text::Style {
+                            color: Some(theme.palette().danger.base.color),
+                        }));
+                }
+
+                if let Some(failure) = &crate_item.failure {
+                    let failure_button = tooltip(
+                        danger_button(text(failure.result.summary()).size(12).font(bold()))
+                            .on_press(Message::ShowCrateLog(crate_item.name.clone())),
+                        "The last operation on this crate failed. Click to view its log",
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    details = details.push(failure_button);
+                }
+
+                if let Some(first) = crate_item.advisories.first() {
+                    let badge_text = if crate_item.advisories.len() > 1 {
+                        format!("{} +{}", first.id, crate_item.advisories.len() - 1)
+                    } else {
+                        first.id.clone()
+                    };
+
+                    let details_text = crate_item
+                        .advisories
+                        .iter()
+                        .map(Advisory::describe)
+                        .collect::<Vec<String>>()
+                        .join("\n");
+
+                    let advisory_badge = tooltip(
+                        container(text(badge_text).size(12).font(bold()).color(Color::WHITE))
+                            .padding([3, 8])
+                            .style(|theme: &Theme| container::Style {
+                                background: Some(theme.palette().danger.base.color.into()),
+                                border: Border {
+                                    radius: 8.into(),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            }),
+                        text(details_text).size(13),
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    details = details.push(advisory_badge);
+                }
+
+                if crate_item.is_yanked() {
+                    let yanked_text = format!("v{} was yanked", crate_item.version);
+
+                    let (yanked_button, yanked_tooltip) = if crate_item.keep_yanked {
+                        (
+                            secondary_button(
+                                text(format!("{yanked_text}, kept")).size(12).font(bold()),
+                            ),
+                            "Click to get updates for this crate again",
+                        )
+                    } else {
+                        (
+                            danger_button(text(yanked_text).size(12).font(bold())),
+                            "Updating is suggested even when pinned. Click to keep this version",
+                        )
+                    };
+
+                    let yanked_button = tooltip(
+                        yanked_button.on_press(Message::ToggleKeepYanked(crate_item.name.clone())),
+                        yanked_tooltip,
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    details = details.push(yanked_button);
+                }
+
+                if let Some(rustc) = self.rustc_for(crate_item)
+                    && let Some(built) = crate_item.outdated_toolchain(rustc)
+                {
+                    let toolchain_badge = tooltip(
+                        container(text(format!("Built with {built}")).size(12))
+                            .padding([2, 6])
+                            .style(|theme: &Theme| {
+                                let palette = theme.palette();
+                                container::Style {
+                                    background: Some(palette.background.weaker.color.into()),
+                                    border: Border {
+                                        radius: 8.into(),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                }
+                            }),
+                        text(format!(
+                            "The active toolchain is {}, rebuild to pick up its fixes",
+                            rustc.summary()
+                        )),
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    details = details.push(toolchain_badge);
+                }
+
+                if let Some(snapshot) = crate_item.rollback_target() {
+                    let mut rollback_button = secondary_button(
+                        text(format!("Roll back to {}", snapshot.label()))
+                            .size(12)
+                            .font(bold()),
+                    );
+
+                    if self.operation.is_none() {
+                        rollback_button =
+                            rollback_button.on_press(Message::Rollback(crate_item.name.clone()));
+                    }
+
+                    let rollback_button = tooltip(
+                        rollback_button,
+                        "Restore the binaries saved before the last update without recompiling",
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    details = details.push(rollback_button);
+                }
+
+                details = details.push(space::horizontal());
+
                 let mut for_removal = false;
 
                 let mut version_text_string = format!("v{}", crate_item.version);
@@ -81,7 +484,10 @@ impl MainWindow {
                         version_text_string = format!("v{} → {}", crate_item.version, latest_local);
                     }
                 } else if let Some(version) = &crate_item.crates_version {
-                    if version > &crate_item.version {
+                    if version > &crate_item.version
+                        || crate_item.yanked_update()
+                        || crate_item.chosen_version.is_some()
+                    {
                         version_text_string = format!("v{} → v{}", crate_item.version, version);
                     } else if version == &crate_item.version {
                         for_removal = true;
@@ -92,7 +498,11 @@ impl MainWindow {
 
                 let version_text = text(version_text_string).font(bold());
 
-                if crate_item.pinned {
+                // A yanked version is replaced even when pinned, as is one picked by hand
+                if crate_item.pinned
+                    && !crate_item.yanked_update()
+                    && crate_item.chosen_version.is_none()
+                {
                     for_removal = true;
                 }
 
@@ -148,44 +558,19 @@ impl MainWindow {
 
                 feature_list = feature_list.push(default_feature);
 
-                // If crate response is found, list the features gotten from crates.io.
-                // If not, if any cached feature list is found, use that.
-                if let Some(crate_response) = &crate_item.crate_response {
-                    let version_data = &crate_response.versions[0];
-
-                    let sorted_features: BTreeSet<String> =
-                        version_data.features.keys().cloned().collect();
-
-                    for feature in sorted_features {
-                        if feature == "default" {
-                            continue;
-                        }
-
-                        let feature_active = crate_item.activated_features.contains(&feature);
-
-                        let feature_button =
-                            toggler_button(text(feature.clone()).size(10), feature_active)
-                                .on_press(Message::FeatureToggle {
-                                    crate_name: crate_item.name.clone(),
-                                    feature_name: feature.clone(),
-                                });
-                        feature_list = feature_list.push(feature_button);
+                for feature in crate_item.available_features() {
+                    if feature == "default" {
+                        continue;
                     }
-                } else {
-                    for feature in &crate_item.cached_features {
-                        if feature == "default" {
-                            continue;
-                        }
 
-                        let feature_active = crate_item.activated_features.contains(feature);
+                    let feature_active = crate_item.activated_features.contains(feature);
 
-                        let feature_button = toggler_button(text(feature).size(10), feature_active)
-                            .on_press(Message::FeatureToggle {
-                                crate_name: crate_item.name.clone(),
-                                feature_name: feature.clone(),
-                            });
-                        feature_list = feature_list.push(feature_button);
-                    }
+                    let feature_button = toggler_button(text(feature).size(10), feature_active)
+                        .on_press(Message::FeatureToggle {
+                            crate_name: crate_item.name.clone(),
+                            feature_name: feature.clone(),
+                        });
+                    feature_list = feature_list.push(feature_button);
                 }
 
                 let feature_layout =
@@ -196,7 +581,7 @@ impl MainWindow {
                         .width(Length::Fill);
 
                 let git_tooltip_content = if let Some(git_link) = &crate_item.git_link {
-                    text(git_link)
+                    text(format!("{git_link} ({})", crate_item.git_ref))
                 } else {
                     text("Enable to use --git flag and install from a git repository")
                 };
@@ -229,16 +614,125 @@ impl MainWindow {
                     }
                 });
 
+                let locked_tooltip = if crate_item.locked.is_some() {
+                    "Whether to use --locked flag when installing (overrides the global default)"
+                } else {
+                    "Whether to use --locked flag when installing (follows the global default)"
+                };
+
                 let locked_button = tooltip(
                     container(
                         toggler_button(
                             lock().size(12).align_x(Alignment::Center),
-                            crate_item.locked,
+                            crate_item.uses_locked(&defaults),
                         )
                         .on_press(Message::ToggleLocked(crate_item.name.clone())),
                     )
                     .align_x(Alignment::End),
-                    "Whether to use --locked flag when installing",
+                    locked_tooltip,
+                    Position::Top,
+                )
+                .style(|theme: &Theme| {
+                    let palette = theme.palette();
+                    container::Style {
+                        background: Some(palette.background.weaker.color.into()),
+                        text_color: Some(palette.background.weak.text),
+                        border: Border {
+                            radius: 8.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                });
+
+                let mut bottom_row = row![feature_layout].spacing(5);
+
+                // Git crates follow a reference instead of releases
+                if crate_item.git_link.is_none() {
+                    let policy_button = tooltip(
+                        toggler_button(
+                            text(crate_item.policy.to_string())
+                                .size(10)
+                                .align_x(Alignment::Center),
+                            crate_item.policy != UpdatePolicy::Any,
+                        )
+                        .on_press(Message::Policy(PolicyEvent::Show(crate_item.name.clone()))),
+                        "Which new releases count as an update",
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    let prerelease_button = tooltip(
+                        toggler_button(
+                            text("pre").size(10).align_x(Alignment::Center),
+                            crate_item.follow_prereleases,
+                        )
+                        .on_press(Message::TogglePrereleases(crate_item.name.clone())),
+                        "Whether prereleases count as updates",
+                        Position::Top,
+                    )
+                    .style(|theme: &Theme| {
+                        let palette = theme.palette();
+                        container::Style {
+                            background: Some(palette.background.weaker.color.into()),
+                            text_color: Some(palette.background.weak.text),
+                            border: Border {
+                                radius: 8.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    });
+
+                    bottom_row = bottom_row.push(policy_button).push(prerelease_button);
+
+                    if !crate_item.versions.is_empty() {
+                        let versions_button = tooltip(
+                            toggler_button(
+                                text("versions").size(10).align_x(Alignment::Center),
+                                crate_item.chosen_version.is_some(),
+                            )
+                            .on_press(Message::VersionPicker(
+                                VersionPickerEvent::Show(crate_item.name.clone()),
+                            )),
+                            "Install a specific version, including older ones",
+                            Position::Top,
+                        )
+                        .style(|theme: &Theme| {
+                            let palette = theme.palette();
+                            container::Style {
+                                background: Some(palette.background.weaker.color.into()),
+                                text_color: Some(palette.background.weak.text),
+                                border: Border {
+                                    radius: 8.into(),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            }
+                        });
+
+                        bottom_row = bottom_row.push(versions_button);
+                    }
+                }
+
+                let build_button = tooltip(
+                    toggler_button(
+                        text("build").size(10).align_x(Alignment::Center),
+                        !crate_item.build.is_empty(),
+                    )
+                    .on_press(Message::Build(BuildEvent::Show(crate_item.name.clone()))),
+                    "Environment, toolchain and extra arguments for the install",
                     Position::Top,
                 )
                 .style(|theme: &Theme| {
@@ -254,6 +748,11 @@ impl MainWindow {
                     }
                 });
 
+                bottom_row = bottom_row
+                    .push(git_button)
+                    .push(locked_button)
+                    .push(build_button);
+
                 let actions = column![
                     version_text.size(15),
                     row![icon_button, pin_button].spacing(5)
@@ -263,11 +762,7 @@ impl MainWindow {
 
                 let card_content = row![details, actions].spacing(10);
 
-                let card_layout = column![
-                    card_content,
-                    row![feature_layout, git_button, locked_button].spacing(5)
-                ]
-                .spacing(5);
+                let card_layout = column![card_content, bottom_row].spacing(5);
 
                 let card = container(card_layout)
                     .style(move |theme: &Theme| {
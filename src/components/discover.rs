@@ -0,0 +1,266 @@
+use iced::border::Radius;
+use iced::widget::scrollable::Scrollbar;
+use iced::widget::{column, container, row, scrollable, space, text, text_input};
+use iced::{Alignment, Border, Color, Element, Length, Padding, Theme};
+
+use crate::discover::SearchResult;
+use crate::git::GitReferenceKind;
+use crate::icon::left_arrow;
+use crate::index::latest_release;
+use crate::message::DiscoverEvent;
+use crate::utils::{bold, input_style, mono, primary_button, toggler_button};
+use crate::{MainWindow, Message};
+
+/// Shorten a download count such as 1234567 to 1.2M
+fn format_downloads(downloads: u64) -> String {
+    match downloads {
+        0..1_000 => downloads.to_string(),
+        1_000..1_000_000 => format!("{:.1}K", downloads as f64 / 1_000.0),
+        _ => format!("{:.1}M", downloads as f64 / 1_000_000.0),
+    }
+}
+
+impl MainWindow {
+    #[must_use]
+    pub fn discover_page(&self) -> Element<'_, Message> {
+        let back_button = container(
+            primary_button(
+                left_arrow()
+                    .align_y(Alignment::Center)
+                    .align_x(Alignment::Center)
+                    .style(|_| text::Style {
+                        color: Some(Color::WHITE),
+                    }),
+            )
+            .on_press(Message::ShowCrates)
+            .width(60),
+        )
+        .padding(5);
+
+        let search_input = text_input("Search crates.io", &self.discover.query)
+            .on_input(|text| Message::Discover(DiscoverEvent::Input(text)))
+            .on_submit(Message::Discover(DiscoverEvent::Search))
+            .padding(5)
+            .style(input_style);
+
+        let search_label = if self.discover.searching {
+            "Searching"
+        } else {
+            "Search"
+        };
+
+        let mut search_button =
+            primary_button(text(search_label).font(bold()).style(|_| text::Style {
+                color: Some(Color::WHITE),
+            }));
+
+        if !self.discover.searching {
+            search_button = search_button.on_press(Message::Discover(DiscoverEvent::Search));
+        }
+
+        let header = row![back_button, search_input, search_button]
+            .spacing(10)
+            .padding(Padding {
+                right: 10.0,
+                ..Default::default()
+            })
+            .align_y(Alignment::Center);
+
+        let mut result_list = column![].spacing(5);
+
+        if let Some(status) = &self.discover.status {
+            result_list = result_list.push(text(status));
+        }
+
+        let result_count = self.discover.results.len();
+
+        for (index, result) in self.discover.results.iter().enumerate() {
+            let card = container(self.discover_card(result))
+                .style(move |theme: &Theme| {
+                    let palette = theme.palette();
+
+                    container::Style {
+                        background: Some(palette.background.base.color.into()),
+                        border: Border {
+                            color: Color::BLACK,
+                            width: 0.5,
+                            radius: Radius {
+                                top_left: if index == 0 { 10.0 } else { 0.0 },
+                                top_right: if index == 0 { 10.0 } else { 0.0 },
+                                bottom_left: if index == result_count - 1 { 10.0 } else { 0.0 },
+                                bottom_right: if index == result_count - 1 { 10.0 } else { 0.0 },
+                            },
+                        },
+                        ..Default::default()
+                    }
+                })
+                .padding(5);
+
+            result_list = result_list.push(card);
+        }
+
+        let body = scrollable(
+            container(result_list)
+                .padding(Padding {
+                    left: 10.0,
+                    right: 15.0,
+                    bottom: 10.0,
+                    ..Default::default()
+                })
+                .width(Length::Fill),
+        )
+        .direction(scrollable::Direction::Vertical(Scrollbar::new()))
+        .height(Length::Fill);
+
+        column![header, body].spacing(10).into()
+    }
+
+    fn discover_card<'a>(&'a self, result: &'a SearchResult) -> Element<'a, Message> {
+        let installed = self.crate_list.contains_key(&result.name);
+        let selected = self.discover.selected.as_ref() == Some(&result.name);
+
+        let details = column![
+            text(&result.name).size(18).font(bold()),
+            text(&result.description)
+                .size(15)
+                .wrapping(text::Wrapping::Glyph),
+            text(format!(
+                "v{} | {} downloads",
+                result.max_version,
+                format_downloads(result.downloads)
+            ))
+            .size(12)
+            .font(mono()),
+        ]
+        .spacing(8)
+        .width(Length::Fill);
+
+        let action: Element<'_, Message> = if installed {
+            text("Installed").size(13).font(bold()).into()
+        } else if self.update_crates.contains_key(&result.name) {
+            text("Queued").size(13).font(bold()).into()
+        } else {
+            toggler_button(text("Install Options").font(bold()), selected)
+                .on_press(Message::Discover(DiscoverEvent::Select(
+                    result.name.clone(),
+                )))
+                .into()
+        };
+
+        let card_content = row![details, action].spacing(10).align_y(Alignment::Start);
+
+        if !selected || installed {
+            return card_content.into();
+        }
+
+        let options: Element<'_, Message> = match self.discover.details.get(&result.name) {
+            None => text("Loading versions and binaries").size(13).into(),
+            Some(Err(e)) => text(e)
+                .size(13)
+                .style(|theme: &Theme| text::Style {
+                    color: Some(theme.palette().danger.base.color),
+                })
+                .into(),
+            Some(Ok(details)) => {
+                let bins = if details.bins.is_empty() {
+                    String::from("Binaries: none, the latest release only has a library")
+                } else {
+                    format!("Binaries: {}", details.bins.join(", "))
+                };
+
+                let mut feature_list = row![].spacing(5);
+
+                feature_list = feature_list.push(
+                    toggler_button(text("default").size(10), !self.discover.no_default_features)
+                        .on_press(Message::Discover(DiscoverEvent::FeatureToggle(
+                            String::from("default"),
+                        ))),
+                );
+
+                let latest = latest_release(&details.versions, false)
+                    .or_else(|| latest_release(&details.versions, true));
+
+                for feature in latest.iter().flat_map(|item| &item.features) {
+                    if feature == "default" {
+                        continue;
+                    }
+
+                    feature_list = feature_list.push(
+                        toggler_button(
+                            text(feature).size(10),
+                            self.discover.activated_features.contains(feature),
+                        )
+                        .on_press(Message::Discover(
+                            DiscoverEvent::FeatureToggle(feature.clone()),
+                        )),
+                    );
+                }
+
+                let feature_layout =
+                    scrollable(container(feature_list).width(Length::Fill).height(30))
+                        .direction(scrollable::Direction::Horizontal(
+                            Scrollbar::new().width(5).scroller_width(5),
+                        ))
+                        .width(Length::Fill);
+
+                let reference_kinds =
+                    GitReferenceKind::ALL
+                        .iter()
+                        .fold(row![].spacing(5), |row, kind| {
+                            row.push(
+                                toggler_button(
+                                    text(kind.to_string()).size(12).align_x(Alignment::Center),
+                                    self.discover.reference_kind == *kind,
+                                )
+                                .on_press(Message::Discover(DiscoverEvent::ReferenceKind(*kind)))
+                                .width(Length::Fill),
+                            )
+                        });
+
+                let git_placeholder = result
+                    .repository
+                    .as_deref()
+                    .unwrap_or("Optional git link to install from");
+
+                let git_input = text_input(git_placeholder, &self.discover.git_link)
+                    .on_input(|text| Message::Discover(DiscoverEvent::GitInput(text)))
+                    .padding(5)
+                    .style(input_style);
+
+                let mut reference_input =
+                    text_input("Branch, tag or commit hash", &self.discover.reference_text)
+                        .padding(5)
+                        .style(input_style);
+
+                if self.discover.reference_kind != GitReferenceKind::DefaultBranch {
+                    reference_input = reference_input
+                        .on_input(|text| Message::Discover(DiscoverEvent::ReferenceInput(text)));
+                }
+
+                let mut install_button =
+                    primary_button(text("Queue Install").font(bold()).style(|_| text::Style {
+                        color: Some(Color::WHITE),
+                    }));
+
+                // Cargo refuses to install crates without binaries
+                if !details.bins.is_empty() || !self.discover.git_link.trim().is_empty() {
+                    install_button =
+                        install_button.on_press(Message::Discover(DiscoverEvent::Install));
+                }
+
+                column![
+                    text(bins).size(13).font(mono()),
+                    feature_layout,
+                    git_input,
+                    reference_kinds,
+                    reference_input,
+                    row![space::horizontal(), install_button],
+                ]
+                .spacing(8)
+                .into()
+            }
+        };
+
+        column![card_content, options].spacing(10).into()
+    }
+}
@@ -0,0 +1,129 @@
+use iced::widget::scrollable::Scrollbar;
+use iced::widget::{column, container, row, scrollable, space, text, text_input};
+use iced::{Alignment, Color, Element, Length, Padding, Theme};
+
+use crate::build_settings::join_words;
+use crate::icon::left_arrow;
+use crate::utils::{bold, input_style, mono, primary_button, toggler_button};
+use crate::{MainWindow, Message};
+
+impl MainWindow {
+    #[must_use]
+    pub fn history_page(&self) -> Element<'_, Message> {
+        let back_button = container(
+            primary_button(
+                left_arrow()
+                    .align_y(Alignment::Center)
+                    .align_x(Alignment::Center)
+                    .style(|_| text::Style {
+                        color: Some(Color::WHITE),
+                    }),
+            )
+            .on_press(Message::ShowCrates)
+            .width(60),
+        )
+        .padding(5);
+
+        let filter_input = text_input(
+            "Filter by crate, version, flag or result",
+            &self.history_filter,
+        )
+        .on_input(Message::HistoryFilter)
+        .padding(5)
+        .style(input_style);
+
+        let header = row![back_button, filter_input]
+            .spacing(10)
+            .padding(Padding {
+                right: 10.0,
+                ..Default::default()
+            })
+            .align_y(Alignment::Center);
+
+        // Newest first
+        let entries = self
+            .history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| entry.matches(&self.history_filter))
+            .fold(column![].spacing(5), |column, (index, entry)| {
+                let mut summary = text(entry.summary()).size(13).font(mono());
+
+                if !entry.success {
+                    summary = summary.style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().danger.base.color),
+                    });
+                }
+
+                column.push(
+                    toggler_button(summary, self.history_selected == Some(index))
+                        .on_press(Message::SelectHistory(index))
+                        .width(Length::Fill),
+                )
+            });
+
+        let entry_list = scrollable(container(entries).padding(Padding {
+            right: 10.0,
+            ..Default::default()
+        }))
+        .direction(scrollable::Direction::Vertical(Scrollbar::new()))
+        .width(Length::FillPortion(2));
+
+        let details: Element<'_, Message> = if let Some(entry) = self
+            .history_selected
+            .and_then(|index| self.history.get(index))
+        {
+            let features = if entry.features.is_empty() {
+                String::from("none")
+            } else {
+                entry.features.join(", ")
+            };
+
+            let command = if entry.command.is_empty() {
+                String::from("none")
+            } else {
+                join_words(&entry.command)
+            };
+
+            column![
+                text(entry.summary()).font(bold()),
+                text(format!("Command: {command}")).size(13).font(mono()),
+                text(format!("Features: {features}")).size(13).font(mono()),
+                scrollable(row![
+                    text(entry.log.join("\n"))
+                        .wrapping(text::Wrapping::Glyph)
+                        .font(mono())
+                        .size(13),
+                    space::horizontal()
+                ])
+                .height(Length::Fill),
+            ]
+            .spacing(8)
+            .width(Length::FillPortion(3))
+            .into()
+        } else {
+            let message = if self.history.is_empty() {
+                "No operations recorded yet"
+            } else {
+                "Select an operation to view its log"
+            };
+
+            container(text(message))
+                .width(Length::FillPortion(3))
+                .into()
+        };
+
+        let body = row![entry_list, details]
+            .spacing(10)
+            .padding(Padding {
+                left: 5.0,
+                right: 5.0,
+                bottom: 5.0,
+                ..Default::default()
+            })
+            .height(Length::Fill);
+
+        column![header, body].spacing(10).into()
+    }
+}
@@ -3,7 +3,7 @@ use iced::widget::{column, container, row, scrollable, space, text};
 use iced::{Alignment, Color, Element, Length, Padding};
 
 use crate::icon::left_arrow;
-use crate::utils::{mono, primary_button};
+use crate::utils::{bold, mono, primary_button};
 use crate::{MainWindow, Message};
 
 impl MainWindow {
@@ -23,7 +23,24 @@ impl MainWindow {
         )
         .padding(5);
 
-        let mut log_text = self.logs.join("\n");
+        let mut header = row![back_button].spacing(10).align_y(Alignment::Center);
+
+        let mut log_text = if let Some(crate_name) = &self.log_filter {
+            let failure = self
+                .crate_list
+                .get(crate_name)
+                .and_then(|item| item.failure.as_ref());
+
+            if let Some(failure) = failure {
+                header = header
+                    .push(text(format!("{crate_name}: {}", failure.result.summary())).font(bold()));
+                failure.log.join("\n")
+            } else {
+                String::new()
+            }
+        } else {
+            self.logs.join("\n")
+        };
 
         if log_text.is_empty() {
             log_text = "No logs to show".to_string();
@@ -49,6 +66,6 @@ impl MainWindow {
         })
         .height(Length::Fill);
 
-        column![back_button, scroll_area].spacing(10).into()
+        column![header, scroll_area].spacing(10).into()
     }
 }
@@ -1,5 +1,7 @@
 mod bottom_bar;
 mod crate_items;
+mod discover;
+mod history;
 mod logs;
 mod modal;
 
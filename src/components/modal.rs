@@ -1,10 +1,16 @@
-use iced::widget::text_input::Status;
 use iced::widget::{column, container, row, scrollable, text, text_input};
-use iced::{Alignment, Border, Color, Element, Length, Theme};
+use iced::{Alignment, Border, Color, Element, Length, Padding, Theme};
 
-use crate::message::GitInputEvent;
-use crate::utils::{bold, danger_button, primary_button};
-use crate::{MainWindow, Message};
+use crate::build_settings::join_words;
+use crate::git::GitReferenceKind;
+use crate::message::{
+    BuildEvent, GitInputEvent, ManifestEvent, PolicyEvent, ReviewEvent, VersionPickerEvent,
+};
+use crate::policy::UpdatePolicyKind;
+use crate::utils::{
+    bold, danger_button, input_style, mono, primary_button, secondary_button, toggler_button,
+};
+use crate::{MainWindow, Message, OperationType};
 
 pub const GIT_MODAL_WIDTH: f64 = 500.0;
 pub const GIT_MODAL_WIDTH_KEY: &str = "git_modal_width";
@@ -12,11 +18,56 @@ pub const GIT_MODAL_WIDTH_KEY: &str = "git_modal_width";
 pub const UPDATE_MODAL_LENGTH: f64 = 500.0;
 pub const UPDATE_MODAL_LENGTH_KEY: &str = "update_modal_length";
 
+pub const POLICY_MODAL_WIDTH: f64 = 500.0;
+pub const POLICY_MODAL_WIDTH_KEY: &str = "policy_modal_width";
+
+pub const BUILD_MODAL_WIDTH: f64 = 500.0;
+pub const BUILD_MODAL_WIDTH_KEY: &str = "build_modal_width";
+
+pub const REVIEW_MODAL_LENGTH: f64 = 600.0;
+pub const REVIEW_MODAL_LENGTH_KEY: &str = "review_modal_length";
+
+pub const VERSION_MODAL_LENGTH: f64 = 500.0;
+pub const VERSION_MODAL_LENGTH_KEY: &str = "version_modal_length";
+
+pub const MANIFEST_MODAL_LENGTH: f64 = 500.0;
+pub const MANIFEST_MODAL_LENGTH_KEY: &str = "manifest_modal_length";
+
 impl MainWindow {
     #[must_use]
     pub fn git_modal(&self) -> Element<'_, Message> {
         let modal_width = self.lerp_state.get(GIT_MODAL_WIDTH_KEY).unwrap_or_default() as u32;
 
+        let reference_kinds = GitReferenceKind::ALL
+            .iter()
+            .fold(row![].spacing(5), |row, kind| {
+                row.push(
+                    toggler_button(
+                        text(kind.to_string()).size(12).align_x(Alignment::Center),
+                        self.git_input.reference_kind == *kind,
+                    )
+                    .on_press(Message::GitInput(GitInputEvent::ReferenceKind(*kind)))
+                    .width(Length::Fill),
+                )
+            });
+
+        let reference_placeholder = match self.git_input.reference_kind {
+            GitReferenceKind::DefaultBranch => "Follows the default branch",
+            GitReferenceKind::Branch => "Branch name",
+            GitReferenceKind::Tag => "Tag name",
+            GitReferenceKind::Rev => "Commit hash",
+        };
+
+        let mut reference_input = text_input(reference_placeholder, &self.git_input.reference_text)
+            .on_submit(Message::GitInput(GitInputEvent::Submit))
+            .padding(5)
+            .style(input_style);
+
+        if self.git_input.reference_kind != GitReferenceKind::DefaultBranch {
+            reference_input = reference_input
+                .on_input(|text| Message::GitInput(GitInputEvent::ReferenceInput(text)));
+        }
+
         container(
             column![
                 column![
@@ -29,46 +80,9 @@ impl MainWindow {
                         .on_input(|text| Message::GitInput(GitInputEvent::Input(text)))
                         .on_submit(Message::GitInput(GitInputEvent::Submit))
                         .padding(5)
-                        .style(|theme: &Theme, status| {
-                            let palette = theme.palette();
-
-                            let active = text_input::Style {
-                                background: (palette.background.base.color).into(),
-                                border: Border {
-                                    radius: 8.0.into(),
-                                    width: 1.0,
-                                    color: palette.background.strong.color,
-                                },
-                                icon: palette.background.weak.text,
-                                placeholder: palette.secondary.base.color,
-                                value: palette.background.base.text,
-                                selection: palette.primary.weak.color,
-                            };
-
-                            match status {
-                                Status::Active => active,
-                                Status::Hovered => text_input::Style {
-                                    border: Border {
-                                        color: palette.background.base.text,
-                                        ..active.border
-                                    },
-                                    ..active
-                                },
-                                Status::Focused { .. } => text_input::Style {
-                                    border: Border {
-                                        color: palette.primary.strong.color,
-                                        ..active.border
-                                    },
-                                    ..active
-                                },
-                                Status::Disabled => text_input::Style {
-                                    background: (palette.background.weak.color).into(),
-                                    value: active.placeholder,
-                                    placeholder: palette.background.strongest.color,
-                                    ..active
-                                },
-                            }
-                        }),
+                        .style(input_style),
+                        reference_kinds,
+                        reference_input,
                     ]
                     .spacing(5),
                     container(
@@ -95,7 +109,444 @@ impl MainWindow {
             .spacing(20),
         )
         .width(modal_width)
-        .height(modal_width / 4)
+        .height(modal_width * 2 / 5)
+        .padding(10)
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    #[must_use]
+    pub fn policy_modal(&self) -> Element<'_, Message> {
+        let modal_width = self
+            .lerp_state
+            .get(POLICY_MODAL_WIDTH_KEY)
+            .unwrap_or_default() as u32;
+
+        let policy_kinds = UpdatePolicyKind::ALL
+            .iter()
+            .fold(row![].spacing(5), |row, kind| {
+                row.push(
+                    toggler_button(
+                        text(kind.to_string()).size(12).align_x(Alignment::Center),
+                        self.policy_input.kind == *kind,
+                    )
+                    .on_press(Message::Policy(PolicyEvent::Kind(*kind)))
+                    .width(Length::Fill),
+                )
+            });
+
+        let (description, placeholder) = match self.policy_input.kind {
+            UpdatePolicyKind::Any => ("Update to every new release", ""),
+            UpdatePolicyKind::Minor => ("Stay on the installed major version", ""),
+            UpdatePolicyKind::Patch => ("Stay on the installed minor version", ""),
+            UpdatePolicyKind::Hold => ("Only update within a version requirement", "~1.4"),
+            UpdatePolicyKind::Ignore => ("Skip one release and update past it", "2.0.0"),
+        };
+
+        let mut value_input = text_input(placeholder, &self.policy_input.text)
+            .on_submit(Message::Policy(PolicyEvent::Submit))
+            .padding(5)
+            .style(input_style);
+
+        if matches!(
+            self.policy_input.kind,
+            UpdatePolicyKind::Hold | UpdatePolicyKind::Ignore
+        ) {
+            value_input = value_input.on_input(|text| Message::Policy(PolicyEvent::Input(text)));
+        }
+
+        let error_text = text(self.policy_input.error.as_deref().unwrap_or_default())
+            .size(13)
+            .style(|theme: &Theme| text::Style {
+                color: Some(theme.palette().danger.base.color),
+            });
+
+        container(
+            column![
+                text("Update Policy").size(20),
+                policy_kinds,
+                text(description).size(13),
+                value_input,
+                error_text,
+                row![
+                    primary_button(
+                        text("Submit")
+                            .color(Color::WHITE)
+                            .font(bold())
+                            .align_x(Alignment::Center)
+                    )
+                    .on_press(Message::Policy(PolicyEvent::Submit))
+                    .width(Length::Fill),
+                    danger_button(text("Cancel").font(bold()).align_x(Alignment::Center))
+                        .on_press(Message::Policy(PolicyEvent::Hide))
+                        .width(Length::Fill),
+                ]
+                .spacing(5),
+            ]
+            .spacing(8),
+        )
+        .width(modal_width)
+        .height(modal_width / 2)
+        .padding(10)
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    #[must_use]
+    pub fn build_modal(&self) -> Element<'_, Message> {
+        let modal_width = self
+            .lerp_state
+            .get(BUILD_MODAL_WIDTH_KEY)
+            .unwrap_or_default() as u32;
+
+        let toolchain_input = text_input("Toolchain such as nightly", &self.build_input.toolchain)
+            .on_input(|text| Message::Build(BuildEvent::Toolchain(text)))
+            .on_submit(Message::Build(BuildEvent::Submit))
+            .padding(5)
+            .style(input_style);
+
+        let env_input = text_input("RUSTFLAGS=\"-C target-cpu=native\"", &self.build_input.env)
+            .on_input(|text| Message::Build(BuildEvent::Env(text)))
+            .on_submit(Message::Build(BuildEvent::Submit))
+            .padding(5)
+            .style(input_style);
+
+        let args_input = text_input("--config profile.release.lto=true", &self.build_input.args)
+            .on_input(|text| Message::Build(BuildEvent::Args(text)))
+            .on_submit(Message::Build(BuildEvent::Submit))
+            .padding(5)
+            .style(input_style);
+
+        let defaults = self
+            .config
+            .as_ref()
+            .map(|config| &config.build_defaults)
+            .filter(|defaults| !defaults.is_empty())
+            .map_or_else(
+                || String::from("No global defaults"),
+                |defaults| {
+                    let mut words = defaults.env_words();
+                    words.extend(defaults.toolchain.iter().map(|t| format!("+{t}")));
+                    words.extend(defaults.extra_args.iter().cloned());
+
+                    format!("Global defaults: {}", join_words(&words))
+                },
+            );
+
+        let error_text = text(self.build_input.error.as_deref().unwrap_or_default())
+            .size(13)
+            .style(|theme: &Theme| text::Style {
+                color: Some(theme.palette().danger.base.color),
+            });
+
+        container(
+            column![
+                text("Build Settings").size(20),
+                text("Toolchain").size(13),
+                toolchain_input,
+                text("Environment variables").size(13),
+                env_input,
+                text("Extra cargo install arguments").size(13),
+                args_input,
+                text(defaults).size(12).font(mono()),
+                error_text,
+                row![
+                    primary_button(
+                        text("Submit")
+                            .color(Color::WHITE)
+                            .font(bold())
+                            .align_x(Alignment::Center)
+                    )
+                    .on_press(Message::Build(BuildEvent::Submit))
+                    .width(Length::Fill),
+                    danger_button(text("Cancel").font(bold()).align_x(Alignment::Center))
+                        .on_press(Message::Build(BuildEvent::Hide))
+                        .width(Length::Fill),
+                ]
+                .spacing(5),
+            ]
+            .spacing(8),
+        )
+        .width(modal_width)
+        .height(modal_width * 4 / 5)
+        .padding(10)
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    #[must_use]
+    pub fn review_modal(&self) -> Element<'_, Message> {
+        let modal_length = self
+            .lerp_state
+            .get(REVIEW_MODAL_LENGTH_KEY)
+            .unwrap_or_default() as u32;
+
+        let mut command_list = column![].spacing(10);
+
+        if self.review.commands.is_empty() {
+            command_list = command_list.push(text("Every command was removed").size(13));
+        }
+
+        for (index, command) in self.review.commands.iter().enumerate() {
+            let action = match command.operation {
+                OperationType::Update => "Install",
+                OperationType::Delete => "Remove",
+                OperationType::Rollback => "Roll back",
+            };
+
+            let mut label = format!("{}. {action} {}", index + 1, command.crate_name);
+
+            if command.edited() {
+                label.push_str(" (edited)");
+            }
+
+            command_list = command_list.push(
+                column![
+                    row![
+                        text(label).size(13).font(bold()).width(Length::Fill),
+                        danger_button(text("Remove").size(10).align_x(Alignment::Center))
+                            .on_press(Message::Review(ReviewEvent::Remove(index))),
+                    ]
+                    .spacing(5)
+                    .align_y(Alignment::Center),
+                    text_input("", &command.text)
+                        .on_input(move |text| Message::Review(ReviewEvent::Input(index, text)))
+                        .font(mono())
+                        .size(13)
+                        .padding(5)
+                        .style(input_style),
+                ]
+                .spacing(5),
+            );
+        }
+
+        let error_text = text(self.review.error.as_deref().unwrap_or_default())
+            .size(13)
+            .style(|theme: &Theme| text::Style {
+                color: Some(theme.palette().danger.base.color),
+            });
+
+        container(
+            column![
+                text("Review Commands").size(20),
+                text("Removals start once every install is done").size(13),
+                scrollable(command_list)
+                    .height(Length::Fill)
+                    .width(Length::Fill),
+                error_text,
+                row![
+                    primary_button(
+                        text("Confirm")
+                            .color(Color::WHITE)
+                            .font(bold())
+                            .align_x(Alignment::Center)
+                    )
+                    .on_press(Message::Review(ReviewEvent::Confirm))
+                    .width(Length::Fill),
+                    secondary_button(text("Dry Run").font(bold()).align_x(Alignment::Center))
+                        .on_press(Message::Review(ReviewEvent::DryRun))
+                        .width(Length::Fill),
+                    danger_button(text("Cancel").font(bold()).align_x(Alignment::Center))
+                        .on_press(Message::Review(ReviewEvent::Hide))
+                        .width(Length::Fill),
+                ]
+                .spacing(5),
+            ]
+            .spacing(10),
+        )
+        .width(modal_length)
+        .height(modal_length)
+        .padding(10)
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    #[must_use]
+    pub fn version_modal(&self) -> Element<'_, Message> {
+        let modal_length = self
+            .lerp_state
+            .get(VERSION_MODAL_LENGTH_KEY)
+            .unwrap_or_default() as u32;
+
+        let mut version_list = column![].spacing(5);
+
+        if let Some(item) = self.crate_list.get(&self.version_picker.crate_name) {
+            // Newest first
+            for release in item.versions.iter().rev() {
+                let mut labels = vec![format!("v{}", release.version)];
+
+                if let Some(date) = item.release_date(release) {
+                    labels.push(date);
+                }
+
+                if let Some(rust_version) = &release.rust_version {
+                    labels.push(format!("rust {rust_version}"));
+                }
+
+                if release.version == item.version {
+                    labels.push(String::from("installed"));
+                }
+
+                if release.yanked {
+                    labels.push(String::from("yanked"));
+                }
+
+                let incompatible = self
+                    .rustc_for(item)
+                    .is_some_and(|rustc| !release.supports(&rustc.version));
+
+                if incompatible {
+                    labels.push(String::from("needs newer rustc"));
+                }
+
+                let mut label = text(labels.join("  ")).size(13).font(mono());
+
+                if release.yanked || incompatible {
+                    label = label.style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().danger.base.color),
+                    });
+                }
+
+                let mut version_button = toggler_button(
+                    label,
+                    item.crates_version.as_ref() == Some(&release.version),
+                )
+                .width(Length::Fill);
+
+                // Cargo refuses yanked releases and reinstalling the same one does nothing
+                if !release.yanked && release.version != item.version {
+                    version_button = version_button.on_press(Message::VersionPicker(
+                        VersionPickerEvent::Select(release.version.clone()),
+                    ));
+                }
+
+                version_list = version_list.push(version_button);
+            }
+        }
+
+        container(
+            column![
+                text(format!("Versions of {}", self.version_picker.crate_name)).size(20),
+                scrollable(container(version_list).padding(Padding {
+                    right: 10.0,
+                    ..Default::default()
+                }))
+                .height(Length::Fill)
+                .width(Length::Fill),
+                danger_button(text("Close").font(bold()).align_x(Alignment::Center))
+                    .on_press(Message::VersionPicker(VersionPickerEvent::Hide))
+                    .width(Length::Fill),
+            ]
+            .spacing(10),
+        )
+        .width(modal_length)
+        .height(modal_length)
+        .padding(10)
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: Border {
+                    radius: 8.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    #[must_use]
+    pub fn manifest_modal(&self) -> Element<'_, Message> {
+        let modal_length = self
+            .lerp_state
+            .get(MANIFEST_MODAL_LENGTH_KEY)
+            .unwrap_or_default() as u32;
+
+        let mut import_button =
+            secondary_button(text("Import").font(bold()).align_x(Alignment::Center))
+                .width(Length::Fill);
+
+        if self.operation.is_none() {
+            import_button = import_button.on_press(Message::Manifest(ManifestEvent::Import));
+        }
+
+        container(
+            column![
+                text("Manifest").size(20),
+                text("Import stages whatever makes the installed crates match the file").size(13),
+                text_input("~/crane.toml", &self.manifest.path)
+                    .on_input(|text| Message::Manifest(ManifestEvent::Input(text)))
+                    .padding(5)
+                    .style(input_style),
+                row![
+                    primary_button(
+                        text("Export")
+                            .color(Color::WHITE)
+                            .font(bold())
+                            .align_x(Alignment::Center)
+                    )
+                    .on_press(Message::Manifest(ManifestEvent::Export))
+                    .width(Length::Fill),
+                    import_button,
+                    danger_button(text("Close").font(bold()).align_x(Alignment::Center))
+                        .on_press(Message::Manifest(ManifestEvent::Hide))
+                        .width(Length::Fill),
+                ]
+                .spacing(5),
+                scrollable(text(self.manifest.status.join("\n")).size(14))
+                    .height(Length::Fill)
+                    .width(Length::Fill),
+            ]
+            .spacing(10),
+        )
+        .width(modal_length)
+        .height(modal_length)
         .padding(10)
         .style(|theme: &Theme| {
             let palette = theme.palette();
@@ -4,10 +4,46 @@ use std::fs::{File, create_dir_all};
 use std::io::{Read as _, Write as _};
 use std::path::PathBuf;
 
+use crate::advisory::default_db_path;
+use crate::build_settings::BuildSettings;
+use crate::index::DEFAULT_INDEX_URL;
+use crate::policy::UpdatePolicy;
+use crate::utils::expand_path;
+
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub crate_rate_limit_ms: u64,
     pub crate_cache: HashMap<String, CrateCache>,
+    /// Use --locked for every crate that does not have its own setting
+    #[serde(default)]
+    pub always_locked: bool,
+    /// Sparse index used for version checks
+    #[serde(default = "default_index_url")]
+    pub index_url: String,
+    /// How many earlier builds of each crate are kept for rollback, 0 turns it off
+    #[serde(default = "default_snapshot_limit")]
+    pub snapshot_limit: usize,
+    /// Local clone of the RustSec advisory database, cargo-audit's clone when unset
+    #[serde(default)]
+    pub advisory_db: Option<String>,
+    /// Update to the newest release the active rustc supports instead of one it cannot build
+    #[serde(default)]
+    pub msrv_fallback: bool,
+    /// How many crates are installed at the same time
+    #[serde(default = "default_parallel_installs")]
+    pub parallel_installs: usize,
+    /// Passed to each install as --jobs, cargo picks one per CPU when unset
+    #[serde(default)]
+    pub build_jobs: Option<usize>,
+    /// Build every install in one target directory, with sccache when it is available
+    #[serde(default)]
+    pub shared_target: bool,
+    /// Environment, toolchain and extra arguments for every install, crates add to them
+    #[serde(default)]
+    pub build_defaults: BuildSettings,
+    /// Which migrations were applied to the file, missing in files from before the first one
+    #[serde(default)]
+    pub config_version: u32,
     #[serde(skip)]
     location: PathBuf,
 }
@@ -18,7 +54,46 @@ pub struct CrateCache {
     pub features: BTreeSet<String>,
     pub crate_version: Option<String>,
     pub pinned: bool,
+    /// None follows the global default
+    pub locked: Option<bool>,
+    /// Stay on a yanked version instead of treating it as an update
+    #[serde(default)]
+    pub keep_yanked: bool,
+    #[serde(default)]
+    pub policy: UpdatePolicy,
+    /// Whether prereleases count as updates
+    #[serde(default)]
+    pub prereleases: bool,
+    #[serde(default)]
+    pub build: BuildSettings,
+}
+
+/// Global install settings that apply to every crate unless overridden
+#[derive(Debug, Clone, Default)]
+pub struct InstallDefaults {
     pub locked: bool,
+    /// How many snapshots to keep per crate before it is updated
+    pub snapshots: usize,
+    /// How many crates are installed at the same time
+    pub parallel: usize,
+    pub jobs: Option<usize>,
+    pub shared_target: bool,
+    pub build: BuildSettings,
+}
+
+/// The version of the config format written by this build
+const CONFIG_VERSION: u32 = 1;
+
+fn default_index_url() -> String {
+    DEFAULT_INDEX_URL.to_string()
+}
+
+fn default_snapshot_limit() -> usize {
+    3
+}
+
+fn default_parallel_installs() -> usize {
+    1
 }
 
 impl Config {
@@ -35,12 +110,23 @@ impl Config {
             File::open(&location).ok()?.read_to_string(&mut buf).ok()?;
             let mut config: Config = serde_json::from_str(&buf).ok()?;
             config.location = location;
+            config.migrate();
             return Some(config);
         }
 
         let config = Config {
             crate_rate_limit_ms: 1000,
             crate_cache: HashMap::new(),
+            always_locked: false,
+            index_url: default_index_url(),
+            snapshot_limit: default_snapshot_limit(),
+            advisory_db: None,
+            msrv_fallback: false,
+            parallel_installs: default_parallel_installs(),
+            build_jobs: None,
+            shared_target: false,
+            build_defaults: BuildSettings::default(),
+            config_version: CONFIG_VERSION,
             location: location.clone(),
         };
 
@@ -51,6 +137,26 @@ impl Config {
         Some(config)
     }
 
+    /// Bring a config written by an older version up to date
+    fn migrate(&mut self) {
+        if self.config_version >= CONFIG_VERSION {
+            return;
+        }
+
+        // Before the lock setting could follow a global default every crate stored false, which
+        // would now override the default instead of following it
+        if self.config_version < 1 {
+            for crate_info in self.crate_cache.values_mut() {
+                if crate_info.locked == Some(false) {
+                    crate_info.locked = None;
+                }
+            }
+        }
+
+        self.config_version = CONFIG_VERSION;
+        self.save();
+    }
+
     fn save(&self) {
         if let Ok(json) = serde_json::to_string_pretty(&self) {
             let _ = File::create(&self.location).and_then(|mut f| f.write_all(json.as_bytes()));
@@ -60,19 +166,24 @@ impl Config {
     pub fn update_cache(
         &mut self,
         crate_name: String,
-        description: String,
-        features: Vec<String>,
+        features: BTreeSet<String>,
         crate_version: String,
     ) {
         let target_crate = self.crate_cache.entry(crate_name).or_default();
 
-        target_crate.description = description;
-        target_crate.features = features.into_iter().collect();
+        target_crate.features = features;
         target_crate.crate_version = Some(crate_version);
 
         self.save();
     }
 
+    pub fn update_description(&mut self, crate_name: String, description: String) {
+        let target_crate = self.crate_cache.entry(crate_name).or_default();
+        target_crate.description = description;
+
+        self.save();
+    }
+
     pub fn update_pinned(&mut self, crate_name: String, pinned: bool) {
         let target_crate = self.crate_cache.entry(crate_name).or_default();
         target_crate.pinned = pinned;
@@ -80,10 +191,76 @@ impl Config {
         self.save();
     }
 
-    pub fn update_locked(&mut self, crate_name: String, locked: bool) {
+    pub fn update_locked(&mut self, crate_name: String, locked: Option<bool>) {
         let target_crate = self.crate_cache.entry(crate_name).or_default();
         target_crate.locked = locked;
 
         self.save();
     }
+
+    pub fn update_keep_yanked(&mut self, crate_name: String, keep_yanked: bool) {
+        let target_crate = self.crate_cache.entry(crate_name).or_default();
+        target_crate.keep_yanked = keep_yanked;
+
+        self.save();
+    }
+
+    pub fn update_policy(&mut self, crate_name: String, policy: UpdatePolicy) {
+        let target_crate = self.crate_cache.entry(crate_name).or_default();
+        target_crate.policy = policy;
+
+        self.save();
+    }
+
+    pub fn update_prereleases(&mut self, crate_name: String, prereleases: bool) {
+        let target_crate = self.crate_cache.entry(crate_name).or_default();
+        target_crate.prereleases = prereleases;
+
+        self.save();
+    }
+
+    pub fn update_build(&mut self, crate_name: String, build: BuildSettings) {
+        let target_crate = self.crate_cache.entry(crate_name).or_default();
+        target_crate.build = build;
+
+        self.save();
+    }
+
+    pub fn update_always_locked(&mut self, always_locked: bool) {
+        self.always_locked = always_locked;
+
+        self.save();
+    }
+
+    pub fn update_msrv_fallback(&mut self, msrv_fallback: bool) {
+        self.msrv_fallback = msrv_fallback;
+
+        self.save();
+    }
+
+    pub fn update_shared_target(&mut self, shared_target: bool) {
+        self.shared_target = shared_target;
+
+        self.save();
+    }
+
+    #[must_use]
+    pub fn install_defaults(&self) -> InstallDefaults {
+        InstallDefaults {
+            locked: self.always_locked,
+            snapshots: self.snapshot_limit,
+            parallel: self.parallel_installs,
+            jobs: self.build_jobs,
+            shared_target: self.shared_target,
+            build: self.build_defaults.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn advisory_db_path(&self) -> Option<PathBuf> {
+        self.advisory_db
+            .as_deref()
+            .map(expand_path)
+            .or_else(default_db_path)
+    }
 }
@@ -0,0 +1,279 @@
+use dirs::home_dir;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::task::{Never, Sipper, sipper};
+use log::error;
+use notify::{Event, RecursiveMode, Watcher};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::Read as _;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::LocalCrate;
+use crate::build_settings::BuildSettings;
+use crate::config::Config;
+use crate::git::GitReference;
+use crate::policy::UpdatePolicy;
+use crate::snapshot::list_snapshots;
+use crate::utils::parse_git_link;
+
+#[derive(Serialize, Deserialize)]
+pub struct CratesFile {
+    pub installs: HashMap<String, InstallInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallInfo {
+    pub version_req: Option<String>,
+    pub bins: Vec<String>,
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+    pub profile: Option<String>,
+    pub target: Option<String>,
+    pub rustc: Option<String>,
+}
+
+#[must_use]
+pub fn cargo_home() -> Option<PathBuf> {
+    let mut target_dir = home_dir()?;
+
+    target_dir.push(".cargo");
+
+    Some(target_dir)
+}
+
+#[must_use]
+pub fn crates_file_path() -> Option<PathBuf> {
+    let mut target_dir = cargo_home()?;
+
+    target_dir.push(".crates2.json");
+
+    Some(target_dir)
+}
+
+/// The older install list cargo still keeps in sync with crates2.json
+#[must_use]
+pub fn crates_toml_path() -> Option<PathBuf> {
+    let mut target_dir = cargo_home()?;
+
+    target_dir.push(".crates.toml");
+
+    Some(target_dir)
+}
+
+#[must_use]
+pub fn crates_file_modified() -> Option<SystemTime> {
+    crates_file_path()?.metadata().ok()?.modified().ok()
+}
+
+/// Signals whenever `.crates2.json` may have changed. The cargo home directory is watched
+/// rather than the file because cargo replaces it on every install.
+pub fn watch_crates_file() -> impl Sipper<Never, ()> {
+    sipper(async |mut output| {
+        let (mut sender, mut receiver) = mpsc::channel(1);
+
+        let watcher = cargo_home().ok_or_else(|| String::from("Failed to get home directory"));
+
+        let watcher = watcher.and_then(|cargo_home| {
+            let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+
+                if event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name().is_some_and(|name| name == ".crates2.json"))
+                {
+                    // A full channel already has a change waiting to be checked
+                    let _ = sender.try_send(());
+                }
+            })
+            .map_err(|e| e.to_string())?;
+
+            watcher
+                .watch(&cargo_home, RecursiveMode::NonRecursive)
+                .map_err(|e| e.to_string())?;
+
+            Ok(watcher)
+        });
+
+        match watcher {
+            // Kept alive for as long as changes are forwarded
+            Ok(_watcher) => {
+                while receiver.next().await.is_some() {
+                    output.send(()).await;
+                }
+
+                error!("Stopped watching .crates2.json");
+            }
+            Err(e) => error!("Failed to watch .crates2.json, checking it periodically: {e}"),
+        }
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            output.send(()).await;
+        }
+    })
+}
+
+pub fn read_crates_file() -> Result<CratesFile, String> {
+    let target_dir = crates_file_path().ok_or("Failed to get home directory")?;
+
+    if !target_dir.exists() {
+        return Err(String::from("No crates2.json file found"));
+    }
+
+    let mut file_content = String::new();
+
+    File::open(&target_dir)
+        .and_then(|mut file| file.read_to_string(&mut file_content))
+        .map_err(|e| format!("Failed to read crates2.json: {e}"))?;
+
+    serde_json::from_str(&file_content).map_err(|e| format!("Failed to parse crates2.json: {e}"))
+}
+
+/// The installed version of a crate, with the commit for git installs
+#[must_use]
+pub fn installed_build(name: &str) -> Option<String> {
+    let crate_file = read_crates_file().ok()?;
+    let prefix = format!("{name} ");
+
+    let key = crate_file
+        .installs
+        .keys()
+        .find(|key| key.starts_with(&prefix))?;
+
+    let mut parts = key.split(' ');
+    let version = parts.nth(1)?;
+
+    match parts.next().and_then(parse_git_link) {
+        Some((_, _, hash)) => Some(format!("v{version} ({})", &hash[..hash.len().min(7)])),
+        None => Some(format!("v{version}")),
+    }
+}
+
+/// Turn the entries of crates2.json into local crates, filling in whatever was cached from
+/// earlier runs
+#[must_use]
+pub fn parse_local_crates(
+    crate_file: CratesFile,
+    config: Option<&Config>,
+) -> BTreeMap<String, LocalCrate> {
+    let mut crate_list = BTreeMap::new();
+
+    for (name, install_info) in crate_file.installs {
+        let split_name = name.split(' ').collect::<Vec<&str>>();
+        if split_name.len() != 3 {
+            error!("Crate name {name} is not recognized. Skipping");
+            continue;
+        }
+
+        let name = split_name[0];
+        let version = split_name[1];
+
+        let source = split_name[2];
+
+        let parsed_info = parse_git_link(source);
+
+        let Ok(version) = Version::parse(version) else {
+            error!("Failed to parse version {version} for crate {name}. Skipping");
+            continue;
+        };
+
+        let mut crates_version = None;
+        let mut cached_features = BTreeSet::new();
+        let mut description = "This crate has no description".to_string();
+        let mut pinned = false;
+        let mut locked = None;
+        let mut keep_yanked = false;
+        let mut policy = UpdatePolicy::default();
+        let mut follow_prereleases = false;
+        let mut build = BuildSettings::default();
+
+        let mut local_hash = None;
+        let mut git_link = None;
+        let mut git_ref = GitReference::DefaultBranch;
+
+        if let Some((link, reference, hash)) = parsed_info {
+            local_hash = Some(hash);
+            git_link = Some(link);
+            git_ref = reference;
+        }
+
+        if let Some(config) = config
+            && let Some(crate_info) = config.crate_cache.get(name)
+        {
+            if let Some(version_string) = &crate_info.crate_version {
+                let version = Version::parse(version_string).unwrap();
+                crates_version = Some(version);
+            }
+
+            cached_features.clone_from(&crate_info.features);
+            description.clone_from(&crate_info.description);
+            pinned = crate_info.pinned;
+            locked = crate_info.locked;
+            keep_yanked = crate_info.keep_yanked;
+            policy.clone_from(&crate_info.policy);
+            follow_prereleases = crate_info.prereleases;
+            build.clone_from(&crate_info.build);
+        }
+
+        // Cargo records the file names, which carry the platform suffix on Windows
+        let bins = install_info
+            .bins
+            .iter()
+            .map(|bin| {
+                bin.strip_suffix(std::env::consts::EXE_SUFFIX)
+                    .unwrap_or(bin)
+                    .to_string()
+            })
+            .collect();
+
+        let local_crate = LocalCrate {
+            name: name.to_string(),
+            description,
+            version,
+            activated_features: install_info.features.iter().cloned().collect(),
+            no_default_features: install_info.no_default_features,
+            crates_version,
+            newest_version: None,
+            policy,
+            follow_prereleases,
+            chosen_version: None,
+            crate_response: None,
+            cached_features,
+            git_link,
+            git_ref,
+            local_hash,
+            latest_hash: None,
+            pinned,
+            locked,
+            version_req: install_info.version_req.clone(),
+            bins,
+            all_features: install_info.all_features,
+            profile: install_info.profile.clone(),
+            target: install_info.target.clone(),
+            failure: None,
+            install_info,
+            versions: Vec::new(),
+            snapshots: list_snapshots(name),
+            advisories: Vec::new(),
+            force: false,
+            rebuild: false,
+            keep_yanked,
+            msrv_limit: None,
+            build,
+            command: None,
+            release_bins: None,
+        };
+
+        crate_list.insert(name.to_string(), local_crate);
+    }
+
+    crate_list
+}
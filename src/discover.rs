@@ -0,0 +1,137 @@
+use crates_io_api::{AsyncClient, CratesQuery};
+use reqwest::{Client, Response};
+use semver::Version;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::index::{IndexVersion, fetch_index, latest_release};
+
+/// How many search results are shown
+const SEARCH_PAGE_SIZE: u64 = 25;
+
+/// A crate found on crates.io that may not be installed
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub name: String,
+    pub description: String,
+    pub downloads: u64,
+    pub max_version: String,
+    pub repository: Option<String>,
+}
+
+/// What is needed to install a search result, fetched once it is opened
+#[derive(Debug, Clone)]
+pub struct CrateDetails {
+    /// Every published version from the sparse index, oldest first
+    pub versions: Vec<IndexVersion>,
+    /// The binaries of the latest release, empty for library only crates
+    pub bins: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: VersionData,
+}
+
+#[derive(Deserialize)]
+struct VersionData {
+    #[serde(default)]
+    bin_names: Option<Vec<String>>,
+}
+
+#[must_use]
+pub fn user_agent() -> String {
+    let app_version = env!("CARGO_PKG_VERSION");
+    format!("Crane/{app_version} (rusty.pickle94@gmail.com)")
+}
+
+/// Search crates.io with the shared HTTP client, so a search does not open new connections
+pub async fn search_crates(
+    client: &Client,
+    query: &str,
+    rate_limit: u64,
+) -> Result<Vec<SearchResult>, String> {
+    let client = AsyncClient::with_http_client(client.clone(), Duration::from_millis(rate_limit));
+
+    let query = CratesQuery::builder()
+        .search(query)
+        .page_size(SEARCH_PAGE_SIZE)
+        .build();
+
+    let page = client
+        .crates(query)
+        .await
+        .map_err(|e| format!("Failed to search crates.io: {e}"))?;
+
+    Ok(page
+        .crates
+        .into_iter()
+        .map(|item| SearchResult {
+            name: item.name,
+            description: item
+                .description
+                .unwrap_or(String::from("The crate has no description")),
+            downloads: item.downloads,
+            max_version: item.max_stable_version.unwrap_or(item.max_version),
+            repository: item.repository,
+        })
+        .collect())
+}
+
+/// The versions of a crate from the index and the binaries of its latest release from the
+/// crates.io API, which is the only place that lists them
+pub async fn fetch_details(
+    client: &Client,
+    index_url: &str,
+    name: &str,
+) -> Result<CrateDetails, String> {
+    let versions = fetch_index(client, index_url, name).await?;
+
+    let Some(latest) = latest_release(&versions, false).or_else(|| latest_release(&versions, true))
+    else {
+        return Err(format!("{name} has no available version"));
+    };
+
+    let bins = fetch_bins(client, name, &latest.version).await?;
+
+    Ok(CrateDetails { bins, versions })
+}
+
+/// The binaries of one release from the crates.io API, which is the only place that lists them
+pub async fn fetch_bins(
+    client: &Client,
+    name: &str,
+    version: &Version,
+) -> Result<Vec<String>, String> {
+    let response = client
+        .get(format!("https://crates.io/api/v1/crates/{name}/{version}"))
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .map_err(|e| format!("Failed to fetch {name}: {e}"))?
+        .json::<VersionResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse the release of {name}: {e}"))?;
+
+    Ok(response.version.bin_names.unwrap_or_default())
+}
+
+/// The binaries of several releases, fetched one after another to stay within the rate limit
+pub async fn fetch_release_bins(
+    client: &Client,
+    releases: Vec<(String, Version)>,
+    rate_limit: u64,
+) -> Vec<(String, Version, Result<Vec<String>, String>)> {
+    let mut results = Vec::new();
+
+    for (index, (name, version)) in releases.into_iter().enumerate() {
+        if index > 0 {
+            tokio::time::sleep(Duration::from_millis(rate_limit)).await;
+        }
+
+        let result = fetch_bins(client, &name, &version).await;
+        results.push((name, version, result));
+    }
+
+    results
+}
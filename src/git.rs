@@ -0,0 +1,281 @@
+use std::fmt;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// How long a remote gets to answer before it counts as unreachable
+const LS_REMOTE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The branch, tag or revision a git crate follows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum GitReference {
+    #[default]
+    DefaultBranch,
+    Branch(String),
+    Tag(String),
+    Rev(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GitReferenceKind {
+    #[default]
+    DefaultBranch,
+    Branch,
+    Tag,
+    Rev,
+}
+
+impl GitReferenceKind {
+    pub const ALL: [GitReferenceKind; 4] = [
+        GitReferenceKind::DefaultBranch,
+        GitReferenceKind::Branch,
+        GitReferenceKind::Tag,
+        GitReferenceKind::Rev,
+    ];
+}
+
+impl fmt::Display for GitReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            GitReferenceKind::DefaultBranch => "Default branch",
+            GitReferenceKind::Branch => "Branch",
+            GitReferenceKind::Tag => "Tag",
+            GitReferenceKind::Rev => "Rev",
+        };
+
+        write!(f, "{label}")
+    }
+}
+
+impl GitReference {
+    /// Build a reference from its kind and name. An empty name falls back to the default branch
+    #[must_use]
+    pub fn new(kind: GitReferenceKind, name: &str) -> Self {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return GitReference::DefaultBranch;
+        }
+
+        match kind {
+            GitReferenceKind::DefaultBranch => GitReference::DefaultBranch,
+            GitReferenceKind::Branch => GitReference::Branch(name.to_string()),
+            GitReferenceKind::Tag => GitReference::Tag(name.to_string()),
+            GitReferenceKind::Rev => GitReference::Rev(name.to_string()),
+        }
+    }
+
+    /// Parse the query part of a cargo git source such as `branch=main`
+    #[must_use]
+    pub fn from_query(query: &str) -> Self {
+        match query.split_once('=') {
+            Some(("branch", name)) => GitReference::Branch(name.to_string()),
+            Some(("tag", name)) => GitReference::Tag(name.to_string()),
+            Some(("rev", name)) => GitReference::Rev(name.to_string()),
+            _ => GitReference::DefaultBranch,
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> GitReferenceKind {
+        match self {
+            GitReference::DefaultBranch => GitReferenceKind::DefaultBranch,
+            GitReference::Branch(_) => GitReferenceKind::Branch,
+            GitReference::Tag(_) => GitReferenceKind::Tag,
+            GitReference::Rev(_) => GitReferenceKind::Rev,
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            GitReference::DefaultBranch => "",
+            GitReference::Branch(name) | GitReference::Tag(name) | GitReference::Rev(name) => name,
+        }
+    }
+
+    /// The flags cargo install needs to build from this reference
+    #[must_use]
+    pub fn install_flags(&self) -> Vec<String> {
+        let flag = match self {
+            GitReference::DefaultBranch => return Vec::new(),
+            GitReference::Branch(_) => "--branch",
+            GitReference::Tag(_) => "--tag",
+            GitReference::Rev(_) => "--rev",
+        };
+
+        vec![flag.to_string(), self.name().to_string()]
+    }
+}
+
+impl fmt::Display for GitReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitReference::DefaultBranch => write!(f, "default branch"),
+            GitReference::Branch(name) => write!(f, "branch {name}"),
+            GitReference::Tag(name) => write!(f, "tag {name}"),
+            GitReference::Rev(name) => write!(f, "rev {name}"),
+        }
+    }
+}
+
+/// Resolve the commit a reference of a repository points to with `git ls-remote`. This works
+/// for any host git can talk to, including SSH and `file://` remotes. A rev is returned as is
+/// since it never moves.
+pub async fn latest_commit(repo_link: &str, reference: &GitReference) -> Result<String, String> {
+    let ref_name = match reference {
+        GitReference::DefaultBranch => String::from("HEAD"),
+        GitReference::Branch(name) => format!("refs/heads/{name}"),
+        GitReference::Tag(name) => format!("refs/tags/{name}"),
+        GitReference::Rev(rev) => return Ok(rev.clone()),
+    };
+
+    // Annotated tags point to a tag object, the peeled entry has the commit cargo records.
+    // git only lists it when it is asked for by name.
+    let peeled = format!("{ref_name}^{{}}");
+
+    let output = Command::new("git")
+        .args(["ls-remote", repo_link, &ref_name, &peeled])
+        // Fail instead of waiting for credentials nobody can type in
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+
+    // One unreachable remote would otherwise hold up the whole check
+    let output = tokio::time::timeout(LS_REMOTE_TIMEOUT, output)
+        .await
+        .map_err(|_| {
+            format!(
+                "git ls-remote timed out after {}s",
+                LS_REMOTE_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(|e| format!("Failed to run git: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git ls-remote failed: {}", stderr.trim()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let refs = stdout
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect::<Vec<(&str, &str)>>();
+
+    refs.iter()
+        .find(|(_, name)| *name == peeled)
+        .or_else(|| refs.iter().find(|(_, name)| *name == ref_name))
+        .map(|(hash, _)| (*hash).to_string())
+        .ok_or_else(|| format!("{reference} not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args([
+                "-c",
+                "user.name=Crane",
+                "-c",
+                "user.email=crane@example.com",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+
+        assert!(output.status.success(), "git {args:?} failed");
+
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// A repository with two commits on main, the first on a branch and behind an annotated tag
+    struct TestRepo {
+        dir: PathBuf,
+        first: String,
+        second: String,
+    }
+
+    impl TestRepo {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("crane-git-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+
+            git(&dir, &["init", "-q", "-b", "main"]);
+            git(&dir, &["commit", "-q", "--allow-empty", "-m", "first"]);
+            let first = git(&dir, &["rev-parse", "HEAD"]);
+
+            git(&dir, &["branch", "stable"]);
+            git(&dir, &["tag", "-a", "v1.0.0", "-m", "release"]);
+
+            git(&dir, &["commit", "-q", "--allow-empty", "-m", "second"]);
+            let second = git(&dir, &["rev-parse", "HEAD"]);
+
+            Self { dir, first, second }
+        }
+
+        fn link(&self) -> String {
+            format!("file://{}", self.dir.display())
+        }
+    }
+
+    impl Drop for TestRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_head_branches_and_tags() {
+        let repo = TestRepo::new("refs");
+        let link = repo.link();
+
+        let head = latest_commit(&link, &GitReference::DefaultBranch).await;
+        assert_eq!(head.unwrap(), repo.second);
+
+        let main = latest_commit(&link, &GitReference::Branch(String::from("main"))).await;
+        assert_eq!(main.unwrap(), repo.second);
+
+        let stable = latest_commit(&link, &GitReference::Branch(String::from("stable"))).await;
+        assert_eq!(stable.unwrap(), repo.first);
+
+        // The peeled commit, not the tag object
+        let tag = latest_commit(&link, &GitReference::Tag(String::from("v1.0.0"))).await;
+        assert_eq!(tag.unwrap(), repo.first);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_references() {
+        let repo = TestRepo::new("missing");
+        let link = repo.link();
+
+        let branch = latest_commit(&link, &GitReference::Branch(String::from("gone"))).await;
+        assert!(branch.is_err());
+
+        let tag = latest_commit(&link, &GitReference::Tag(String::from("v9"))).await;
+        assert!(tag.is_err());
+
+        let missing_repo = format!("{link}-missing");
+        assert!(
+            latest_commit(&missing_repo, &GitReference::DefaultBranch)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn rev_is_returned_as_is() {
+        let rev = GitReference::Rev(String::from("abc1234"));
+        assert_eq!(
+            latest_commit("file:///nowhere", &rev).await.unwrap(),
+            "abc1234"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions, create_dir_all};
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryAction {
+    Install,
+    Uninstall,
+    Rollback,
+}
+
+impl std::fmt::Display for HistoryAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            HistoryAction::Install => "install",
+            HistoryAction::Uninstall => "uninstall",
+            HistoryAction::Rollback => "rollback",
+        };
+
+        write!(f, "{label}")
+    }
+}
+
+/// How many entries the history keeps, older ones are dropped since each carries a full log
+pub const HISTORY_LIMIT: usize = 500;
+
+/// A finished operation on a crate as kept in the history file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub crate_name: String,
+    pub action: HistoryAction,
+    /// The installed build before the operation
+    pub from: Option<String>,
+    /// The installed build after the operation
+    pub to: Option<String>,
+    pub features: Vec<String>,
+    pub command: Vec<String>,
+    /// Seconds since the unix epoch
+    pub started: u64,
+    pub duration_ms: u64,
+    pub result: String,
+    pub success: bool,
+    pub log: Vec<String>,
+}
+
+impl HistoryEntry {
+    /// One line describing the entry for the history list
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let change = match (&self.from, &self.to) {
+            (Some(from), Some(to)) if from != to => format!("{from} → {to}"),
+            (Some(build), _) | (None, Some(build)) => build.clone(),
+            (None, None) => String::new(),
+        };
+
+        format!(
+            "{}  {} {} {change}  {} in {}",
+            format_timestamp(self.started),
+            self.action,
+            self.crate_name,
+            self.result,
+            format_duration(self.duration_ms)
+        )
+    }
+
+    /// Whether the entry mentions the filter text anywhere a user would search for
+    #[must_use]
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+
+        [
+            self.crate_name.as_str(),
+            &self.action.to_string(),
+            self.from.as_deref().unwrap_or_default(),
+            self.to.as_deref().unwrap_or_default(),
+            &self.command.join(" "),
+            &self.result,
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(&filter))
+    }
+}
+
+fn history_location() -> Option<PathBuf> {
+    let mut location = dirs::data_local_dir()?;
+    location.push("crane");
+
+    create_dir_all(&location).ok()?;
+
+    location.push("history.jsonl");
+
+    Some(location)
+}
+
+/// The latest recorded operations, oldest first. The file is cut down to them when it grew
+/// past the limit.
+#[must_use]
+pub fn load_history() -> Vec<HistoryEntry> {
+    let Some(location) = history_location() else {
+        return Vec::new();
+    };
+
+    let Ok(file) = File::open(&location) else {
+        return Vec::new();
+    };
+
+    let mut lines = VecDeque::with_capacity(HISTORY_LIMIT);
+    let mut dropped = false;
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if lines.len() == HISTORY_LIMIT {
+            lines.pop_front();
+            dropped = true;
+        }
+
+        lines.push_back(line);
+    }
+
+    if dropped {
+        rotate_history(&location, &lines);
+    }
+
+    lines
+        .into_iter()
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                error!("Failed to parse history entry: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Rewrite the history file with only the kept lines, swapping it in with a rename
+fn rotate_history(location: &Path, lines: &VecDeque<String>) {
+    let temp = location.with_extension("jsonl.tmp");
+
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            for line in lines {
+                writeln!(file, "{line}")?;
+            }
+
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, location));
+
+    if let Err(e) = result {
+        error!("Failed to trim history: {e}");
+        let _ = fs::remove_file(&temp);
+    }
+}
+
+pub fn append_history(entry: &HistoryEntry) {
+    let Some(location) = history_location() else {
+        return;
+    };
+
+    let Ok(json) = serde_json::to_string(entry) else {
+        return;
+    };
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(location)
+        .and_then(|mut file| writeln!(file, "{json}"));
+
+    if let Err(e) = result {
+        error!("Failed to write history: {e}");
+    }
+}
+
+#[must_use]
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Format seconds since the unix epoch as a UTC date and time
+#[must_use]
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+
+    // Convert days since the epoch to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        time / 3600,
+        (time % 3600) / 60
+    )
+}
+
+#[must_use]
+pub fn format_duration(duration_ms: u64) -> String {
+    let secs = duration_ms / 1000;
+
+    if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}.{}s", secs, (duration_ms % 1000) / 100)
+    }
+}
@@ -0,0 +1,426 @@
+use log::error;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{File, create_dir_all};
+use std::io::{Read as _, Write as _};
+use std::path::{Path, PathBuf};
+
+use crate::toolchain::supports;
+
+pub const DEFAULT_INDEX_URL: &str = "https://index.crates.io";
+
+/// A published version of a crate as listed in the sparse index
+#[derive(Debug, Clone)]
+pub struct IndexVersion {
+    pub version: Version,
+    pub features: BTreeSet<String>,
+    pub yanked: bool,
+    pub rust_version: Option<String>,
+    /// The publish date, only recorded for releases published since the index started
+    /// tracking it
+    pub published: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IndexLine {
+    vers: String,
+    #[serde(default)]
+    deps: Vec<IndexDependency>,
+    #[serde(default)]
+    features: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    features2: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    rust_version: Option<String>,
+    #[serde(default)]
+    pubtime: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IndexDependency {
+    name: String,
+    #[serde(default)]
+    optional: bool,
+}
+
+/// The last response of the index for a crate, kept to make conditional requests
+#[derive(Serialize, Deserialize)]
+struct CachedEntry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// The path of a crate inside the index, following the layout cargo uses
+#[must_use]
+pub fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+
+    match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    }
+}
+
+/// Parse an index file into its versions, oldest first
+#[must_use]
+pub fn parse_index(body: &str) -> Vec<IndexVersion> {
+    let mut versions = Vec::new();
+
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        let parsed: IndexLine = match serde_json::from_str(line) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                error!("Failed to parse index line: {e}");
+                continue;
+            }
+        };
+
+        let Ok(version) = Version::parse(&parsed.vers) else {
+            error!("Failed to parse index version {}", parsed.vers);
+            continue;
+        };
+
+        let mut features: BTreeSet<String> = parsed
+            .features
+            .keys()
+            .chain(parsed.features2.keys())
+            .cloned()
+            .collect();
+
+        // Optional dependencies are features too unless something refers to them with dep:
+        let explicit_deps: HashSet<&str> = parsed
+            .features
+            .values()
+            .chain(parsed.features2.values())
+            .flatten()
+            .filter_map(|value| value.strip_prefix("dep:"))
+            .collect();
+
+        for dep in parsed.deps.iter().filter(|dep| dep.optional) {
+            if !explicit_deps.contains(dep.name.as_str()) {
+                features.insert(dep.name.clone());
+            }
+        }
+
+        versions.push(IndexVersion {
+            version,
+            features,
+            yanked: parsed.yanked,
+            rust_version: parsed.rust_version,
+            published: parsed
+                .pubtime
+                .and_then(|time| time.get(..10).map(ToString::to_string)),
+        });
+    }
+
+    versions.sort_by(|a, b| a.version.cmp(&b.version));
+
+    versions
+}
+
+impl IndexVersion {
+    #[must_use]
+    pub fn is_prerelease(&self) -> bool {
+        !self.version.pre.is_empty()
+    }
+
+    /// Whether the toolchain meets the `rust-version` of the release. Releases that do not
+    /// declare one are assumed to build.
+    #[must_use]
+    pub fn supports(&self, rustc: &Version) -> bool {
+        self.rust_version
+            .as_deref()
+            .is_none_or(|rust_version| supports(rustc, rust_version))
+    }
+}
+
+/// The newest version that has not been yanked, skipping prereleases unless they are followed
+#[must_use]
+pub fn latest_release(versions: &[IndexVersion], prereleases: bool) -> Option<&IndexVersion> {
+    versions
+        .iter()
+        .rev()
+        .find(|version| !version.yanked && (prereleases || !version.is_prerelease()))
+}
+
+/// The newest prerelease that has not been yanked
+#[must_use]
+pub fn latest_prerelease(versions: &[IndexVersion]) -> Option<&IndexVersion> {
+    versions
+        .iter()
+        .rev()
+        .find(|version| !version.yanked && version.is_prerelease())
+}
+
+fn cache_location(name: &str) -> Option<PathBuf> {
+    let mut location = dirs::data_local_dir()?;
+    location.push("crane");
+    location.push("index");
+
+    create_dir_all(&location).ok()?;
+
+    location.push(format!("{}.json", name.to_lowercase()));
+
+    Some(location)
+}
+
+fn read_cache(location: &Path) -> Option<CachedEntry> {
+    let mut buf = String::new();
+    File::open(location).ok()?.read_to_string(&mut buf).ok()?;
+
+    serde_json::from_str(&buf).ok()
+}
+
+fn write_cache(location: &Path, entry: &CachedEntry) {
+    if let Ok(json) = serde_json::to_string(entry) {
+        let _ = File::create(location).and_then(|mut f| f.write_all(json.as_bytes()));
+    }
+}
+
+/// Fetch every version of a crate from the sparse index. The previous response is sent back
+/// as `ETag` and `Last-Modified` so an unchanged crate costs a 304 with no body.
+pub async fn fetch_index(
+    client: &Client,
+    index_url: &str,
+    name: &str,
+) -> Result<Vec<IndexVersion>, String> {
+    fetch_index_cached(client, index_url, name, cache_location(name).as_deref()).await
+}
+
+/// Fetch the versions of a crate, keeping the response at the given cache location
+async fn fetch_index_cached(
+    client: &Client,
+    index_url: &str,
+    name: &str,
+    cache: Option<&Path>,
+) -> Result<Vec<IndexVersion>, String> {
+    let url = format!("{}/{}", index_url.trim_end_matches('/'), index_path(name));
+
+    let cached = cache.and_then(read_cache);
+
+    let mut request = client.get(&url);
+
+    if let Some(cached) = &cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {url}: {e}"))?;
+
+    if response.status() == StatusCode::NOT_MODIFIED
+        && let Some(cached) = cached
+    {
+        return Ok(parse_index(&cached.body));
+    }
+
+    if !response.status().is_success() {
+        return Err(format!("{url} returned {}", response.status()));
+    }
+
+    let header_value = |header| {
+        response
+            .headers()
+            .get(header)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    let etag = header_value(ETAG);
+    let last_modified = header_value(LAST_MODIFIED);
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {url}: {e}"))?;
+
+    let versions = parse_index(&body);
+
+    if let Some(cache) = cache {
+        write_cache(
+            cache,
+            &CachedEntry {
+                etag,
+                last_modified,
+                body,
+            },
+        );
+    }
+
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const INDEX_FILE: &str = r#"{"name":"demo","vers":"1.0.0","deps":[],"features":{"default":["std"],"std":[]},"yanked":false}
+{"name":"demo","vers":"1.2.0","deps":[{"name":"serde","optional":true},{"name":"rayon","optional":true},{"name":"log","optional":false}],"features":{"parallel":["dep:rayon"]},"yanked":false,"rust_version":"1.70","pubtime":"2024-05-01T10:00:00Z"}
+{"name":"demo","vers":"1.1.0","deps":[],"features":{},"yanked":false}
+not json
+{"name":"demo","vers":"not.a.version","deps":[],"features":{}}
+{"name":"demo","vers":"1.3.0","deps":[],"features":{},"yanked":true}
+{"name":"demo","vers":"2.0.0-beta.1","deps":[],"features":{},"features2":{"new":[]},"yanked":false}
+"#;
+
+    fn versions_of(versions: &[IndexVersion]) -> Vec<String> {
+        versions.iter().map(|v| v.version.to_string()).collect()
+    }
+
+    #[test]
+    fn index_path_follows_cargo_layout() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("ab"), "2/ab");
+        assert_eq!(index_path("abc"), "3/a/abc");
+        assert_eq!(index_path("serde"), "se/rd/serde");
+        assert_eq!(index_path("Cargo-Edit"), "ca/rg/cargo-edit");
+    }
+
+    #[test]
+    fn parse_index_skips_bad_lines_and_sorts() {
+        let versions = parse_index(INDEX_FILE);
+
+        assert_eq!(
+            versions_of(&versions),
+            ["1.0.0", "1.1.0", "1.2.0", "1.3.0", "2.0.0-beta.1"]
+        );
+    }
+
+    #[test]
+    fn parse_index_reads_features_and_metadata() {
+        let versions = parse_index(INDEX_FILE);
+        let release = versions
+            .iter()
+            .find(|v| v.version == Version::new(1, 2, 0))
+            .unwrap();
+
+        // rayon is only reachable through dep:, log is not optional
+        let features = release.features.iter().cloned().collect::<Vec<String>>();
+        assert_eq!(features, ["parallel", "serde"]);
+        assert_eq!(release.rust_version.as_deref(), Some("1.70"));
+        assert_eq!(release.published.as_deref(), Some("2024-05-01"));
+
+        let beta = versions.last().unwrap();
+        assert!(beta.is_prerelease());
+        assert!(beta.features.contains("new"));
+        assert!(versions.iter().any(|v| v.yanked));
+    }
+
+    #[test]
+    fn latest_release_skips_yanked_and_prereleases() {
+        let versions = parse_index(INDEX_FILE);
+
+        let stable = latest_release(&versions, false).unwrap();
+        assert_eq!(stable.version, Version::new(1, 2, 0));
+
+        let any = latest_release(&versions, true).unwrap();
+        assert_eq!(any.version.to_string(), "2.0.0-beta.1");
+
+        let pre = latest_prerelease(&versions).unwrap();
+        assert_eq!(pre.version.to_string(), "2.0.0-beta.1");
+
+        assert!(latest_release(&[], true).is_none());
+    }
+
+    /// Serve the index file once with an ETag, then answer 304 to requests that send it back
+    async fn serve_index(not_modified: Arc<AtomicUsize>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+
+                while !request.ends_with(b"\r\n\r\n") {
+                    let Ok(read) = stream.read(&mut buf).await else {
+                        break;
+                    };
+
+                    if read == 0 {
+                        break;
+                    }
+
+                    request.extend_from_slice(&buf[..read]);
+                }
+
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    not_modified.fetch_add(1, Ordering::SeqCst);
+                    String::from(
+                        "HTTP/1.1 304 Not Modified\r\nConnection: close\r\nETag: \"v1\"\r\n\r\n",
+                    )
+                } else if request.starts_with("get /de/mo/demo ") {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nConnection: close\r\nETag: \"v1\"\r\nContent-Length: {}\r\n\r\n{INDEX_FILE}",
+                        INDEX_FILE.len()
+                    )
+                } else {
+                    String::from(
+                        "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+                    )
+                };
+
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn unchanged_index_is_read_from_the_cache() {
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let index_url = serve_index(not_modified.clone()).await;
+
+        let cache =
+            std::env::temp_dir().join(format!("crane-index-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&cache);
+
+        let client = Client::new();
+
+        let first = fetch_index_cached(&client, &index_url, "demo", Some(&cache))
+            .await
+            .unwrap();
+        assert_eq!(not_modified.load(Ordering::SeqCst), 0);
+
+        let cached = read_cache(&cache).unwrap();
+        assert_eq!(cached.etag.as_deref(), Some("\"v1\""));
+
+        let second = fetch_index_cached(&client, &index_url, "demo", Some(&cache))
+            .await
+            .unwrap();
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+        assert_eq!(versions_of(&first), versions_of(&second));
+
+        let missing = fetch_index_cached(&client, &index_url, "nope", None).await;
+        assert!(missing.is_err());
+
+        let _ = std::fs::remove_file(&cache);
+    }
+}
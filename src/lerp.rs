@@ -81,4 +81,13 @@ impl LerpState {
     pub fn get(&self, id: &str) -> Option<f64> {
         self.values.get(id).map(|value| value.current)
     }
+
+    /// Forget a value that is no longer shown
+    pub fn remove(&mut self, id: &str) {
+        if let Some(value) = self.values.remove(id)
+            && value.active
+        {
+            self.active_lerp_count = self.active_lerp_count.saturating_sub(1);
+        }
+    }
 }
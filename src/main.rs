@@ -1,40 +1,85 @@
+mod advisory;
+mod build_cache;
+mod build_settings;
+mod cli;
 mod components;
 mod config;
+mod crates_file;
+mod discover;
+mod git;
+mod history;
 mod icon;
+mod index;
 mod lerp;
+mod manifest;
 mod message;
+mod policy;
+mod progress;
+mod snapshot;
+mod toolchain;
 mod utils;
 mod worker;
 
 use crates_io_api::CrateResponse;
-use dirs::home_dir;
+use iced::futures::SinkExt;
 use iced::futures::channel::mpsc::Sender;
 use iced::widget::column;
-use iced::{Element, Subscription, Theme, time};
+use iced::{Element, Subscription, Task, Theme, time};
 use log::{LevelFilter, error, info};
 use semver::Version;
-use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::fs::File;
-use std::io::Read as _;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+use crate::advisory::{Advisory, ScanTarget};
+use crate::build_settings::BuildSettings;
 use crate::components::{
-    FETCH_PROGRESS_HEIGHT_KEY, GIT_MODAL_WIDTH_KEY, OPERATION_CONTAINER, OPERATION_CONTAINER_KEY,
-    OPERATION_PROGRESS_KEY, UPDATE_MODAL_LENGTH, UPDATE_MODAL_LENGTH_KEY,
+    BUILD_MODAL_WIDTH_KEY, BUILD_PROGRESS_HEIGHT, FETCH_PROGRESS_HEIGHT, FETCH_PROGRESS_HEIGHT_KEY,
+    FETCH_PROGRESS_KEY, GIT_MODAL_WIDTH_KEY, MANIFEST_MODAL_LENGTH_KEY, OPERATION_CONTAINER,
+    OPERATION_CONTAINER_KEY, OPERATION_PROGRESS_KEY, POLICY_MODAL_WIDTH_KEY,
+    REVIEW_MODAL_LENGTH_KEY, UPDATE_MODAL_LENGTH, UPDATE_MODAL_LENGTH_KEY,
+    VERSION_MODAL_LENGTH_KEY,
 };
-use crate::config::Config;
+use crate::config::{Config, InstallDefaults};
+use crate::crates_file::{
+    InstallInfo, cargo_home, crates_file_modified, parse_local_crates, read_crates_file,
+    watch_crates_file,
+};
+use crate::git::GitReference;
+use crate::history::{HistoryEntry, load_history};
+use crate::index::{IndexVersion, latest_prerelease, latest_release};
 use crate::lerp::LerpState;
-use crate::message::{GitInputEvent, GitInputState, Message};
-use crate::utils::{modal, parse_git_link};
-use crate::worker::{WorkerInput, event_worker};
+use crate::manifest::Manifest;
+use crate::message::{
+    BuildEvent, BuildInputState, DiscoverState, GitInputEvent, GitInputState, ManifestEvent,
+    ManifestState, Message, PolicyEvent, PolicyInputState, ReviewCommand, ReviewEvent, ReviewState,
+    VersionPickerEvent, VersionPickerState,
+};
+use crate::policy::UpdatePolicy;
+use crate::progress::BuildProgress;
+use crate::snapshot::Snapshot;
+use crate::toolchain::{Rustc, host_triple};
+use crate::utils::modal;
+use crate::worker::{CommandResult, WorkerInput, event_worker, install_command, uninstall_command};
 
 pub fn main() -> iced::Result {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    // Any argument runs a command without opening the window, where only problems are logged
+    let log_level = if args.is_empty() {
+        LevelFilter::Info
+    } else {
+        LevelFilter::Warn
+    };
+
     pretty_env_logger::formatted_timed_builder()
         .format_timestamp_millis()
-        .filter_module(env!("CARGO_BIN_NAME"), LevelFilter::Info)
+        .filter_module(env!("CARGO_BIN_NAME"), log_level)
         .init();
 
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     iced::application(MainWindow::new, MainWindow::update, MainWindow::view)
         .subscription(MainWindow::subscription)
         .font(icon::FONT)
@@ -53,22 +98,52 @@ pub struct MainWindow {
     lerp_state: LerpState,
     update_crates: HashMap<String, LocalCrate>,
     delete_crates: HashMap<String, LocalCrate>,
-    operation_crate: Option<OperationCrate>,
+    /// The kind of batch that is running
+    operation: Option<OperationType>,
+    /// The crates of the batch being worked on right now
+    operation_crates: Vec<OperationCrate>,
+    /// How many crates of the batch are done
+    operation_done: usize,
+    stopping_operation: bool,
+    operation_cancelled: bool,
     logs: Vec<String>,
+    operation_logs: HashMap<String, Vec<String>>,
+    log_filter: Option<String>,
+    history: Vec<HistoryEntry>,
+    history_filter: String,
+    /// Index into the history of the entry whose log is open
+    history_selected: Option<usize>,
     git_input: GitInputState,
+    policy_input: PolicyInputState,
+    build_input: BuildInputState,
+    review: ReviewState,
+    version_picker: VersionPickerState,
+    discover: DiscoverState,
+    /// The active rustc, once detected
+    rustc: Option<Rustc>,
+    /// The rustc of every toolchain named in the build settings
+    toolchains: HashMap<String, Rustc>,
+    /// Bytes used by the shared target directory, once measured
+    build_cache_size: Option<u64>,
+    sccache: bool,
+    manifest: ManifestState,
     config: Option<Config>,
     update_available: Option<String>,
+    crates_file_modified: Option<SystemTime>,
 }
 
 pub struct OperationCrate {
     name: String,
-    operation_type: OperationType,
-    index: usize,
+    started: Instant,
+    /// How far cargo got with building the crate, once it started
+    build: Option<BuildProgress>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationType {
     Update,
     Delete,
+    Rollback,
 }
 
 #[derive(Clone, Default, Copy, PartialEq, Eq)]
@@ -76,23 +151,8 @@ pub enum Page {
     #[default]
     Crates,
     Logs,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct CratesFile {
-    pub installs: HashMap<String, InstallInfo>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct InstallInfo {
-    pub version_req: Option<String>,
-    pub bins: Vec<String>,
-    pub features: Vec<String>,
-    pub all_features: bool,
-    pub no_default_features: bool,
-    pub profile: Option<String>,
-    pub target: Option<String>,
-    pub rustc: Option<String>,
+    History,
+    Discover,
 }
 
 #[derive(Debug, Clone)]
@@ -102,105 +162,441 @@ pub struct LocalCrate {
     pub activated_features: HashSet<String>,
     pub no_default_features: bool,
     version: Version,
+    /// The newest release the update policy allows, which an update installs
     crates_version: Option<Version>,
+    /// The newest release regardless of the update policy
+    newest_version: Option<Version>,
+    policy: UpdatePolicy,
+    /// Whether prereleases count as updates
+    follow_prereleases: bool,
+    /// A release picked by hand, which replaces the update target while it is staged
+    chosen_version: Option<Version>,
     crate_response: Option<CrateResponse>,
     cached_features: BTreeSet<String>,
     git_link: Option<String>,
+    git_ref: GitReference,
     pinned: bool,
     local_hash: Option<String>,
     latest_hash: Option<String>,
-    locked: bool,
+    locked: Option<bool>,
+    version_req: Option<String>,
+    bins: Vec<String>,
+    all_features: bool,
+    profile: Option<String>,
+    target: Option<String>,
+    failure: Option<CrateFailure>,
+    /// The crates2.json entry the crate was loaded from
+    install_info: InstallInfo,
+    /// Every published version from the sparse index, oldest first
+    versions: Vec<IndexVersion>,
+    /// Earlier builds that can be restored, newest first
+    snapshots: Vec<Snapshot>,
+    /// Known vulnerabilities in the installed build, from the last advisory scan
+    advisories: Vec<Advisory>,
+    /// Reinstall even when the same version is installed so the dependencies are rebuilt
+    force: bool,
+    /// Reinstall the installed version instead of updating, to build it with the active rustc
+    rebuild: bool,
+    /// Stay on the installed version even though it was yanked
+    keep_yanked: bool,
+    /// The active rustc when updates fall back to releases it can build
+    msrv_limit: Option<Version>,
+    /// Environment, toolchain and extra arguments on top of the global defaults
+    build: BuildSettings,
+    /// A command edited in the review, run instead of the generated one
+    command: Option<Vec<String>>,
+    /// The binaries of the installed release, from crates.io
+    release_bins: Option<(Version, Vec<String>)>,
 }
 
-impl MainWindow {
-    fn new() -> Self {
-        let Some(mut target_dir) = home_dir() else {
-            println!("Failed to get home directory. Exiting");
-            std::process::exit(1);
+/// The outcome and output of the last operation on a crate that did not succeed
+#[derive(Debug, Clone)]
+pub struct CrateFailure {
+    result: CommandResult,
+    log: Vec<String>,
+}
+
+impl LocalCrate {
+    /// A crate that is not installed yet, staged so it can go through the usual install
+    #[must_use]
+    pub fn not_installed(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: String::from("Not installed yet"),
+            activated_features: HashSet::new(),
+            no_default_features: false,
+            version: Version::new(0, 0, 0),
+            crates_version: None,
+            newest_version: None,
+            policy: UpdatePolicy::default(),
+            follow_prereleases: false,
+            chosen_version: None,
+            crate_response: None,
+            cached_features: BTreeSet::new(),
+            git_link: None,
+            git_ref: GitReference::DefaultBranch,
+            pinned: false,
+            local_hash: None,
+            latest_hash: None,
+            locked: None,
+            version_req: None,
+            bins: Vec::new(),
+            all_features: false,
+            profile: None,
+            target: None,
+            failure: None,
+            install_info: InstallInfo::default(),
+            versions: Vec::new(),
+            snapshots: Vec::new(),
+            advisories: Vec::new(),
+            force: false,
+            rebuild: false,
+            keep_yanked: false,
+            msrv_limit: None,
+            build: BuildSettings::default(),
+            command: None,
+            release_bins: None,
+        }
+    }
+
+    /// Whether --locked is used, falling back to the global default when the crate has no
+    /// setting of its own
+    #[must_use]
+    pub fn uses_locked(&self, defaults: &InstallDefaults) -> bool {
+        self.locked.unwrap_or(defaults.locked)
+    }
+
+    /// The repository and reference to check for new commits
+    #[must_use]
+    pub fn git_source(&self) -> Option<(String, GitReference)> {
+        self.git_link
+            .clone()
+            .map(|link| (link, self.git_ref.clone()))
+    }
+
+    /// Whether a newer version or commit than the installed one is known
+    #[must_use]
+    pub fn has_update(&self) -> bool {
+        if self.git_link.is_some() {
+            return self.latest_hash.is_some() && self.latest_hash != self.local_hash;
+        }
+
+        self.crates_version
+            .as_ref()
+            .is_some_and(|latest| latest > &self.version)
+    }
+
+    /// Whether the installed version was yanked from the registry
+    #[must_use]
+    pub fn is_yanked(&self) -> bool {
+        self.git_link.is_none()
+            && self
+                .versions
+                .iter()
+                .any(|item| item.version == self.version && item.yanked)
+    }
+
+    /// Whether the installed version was yanked and a newer release can replace it. This
+    /// applies even to pinned crates unless the yanked version is explicitly kept, an older
+    /// release has to be picked by hand since it would be a downgrade.
+    #[must_use]
+    pub fn yanked_update(&self) -> bool {
+        self.is_yanked()
+            && !self.keep_yanked
+            && self
+                .crates_version
+                .as_ref()
+                .is_some_and(|latest| latest > &self.version)
+    }
+
+    /// Whether an update of every crate should include this one
+    #[must_use]
+    pub fn should_update(&self) -> bool {
+        (!self.pinned && self.has_update()) || self.yanked_update()
+    }
+
+    /// Store the published versions and pick the update target the policy allows
+    pub fn set_versions(&mut self, versions: Vec<IndexVersion>) {
+        self.versions = versions;
+        self.apply_policy();
+    }
+
+    /// Pick the newest release the policy allows as the update target once the published
+    /// versions are known
+    pub fn apply_policy(&mut self) {
+        if self.versions.is_empty() {
+            return;
+        }
+
+        self.newest_version = latest_release(&self.versions, self.follow_prereleases)
+            .map(|item| item.version.clone());
+        self.crates_version = self.chosen_version.clone().or_else(|| {
+            self.policy
+                .latest_allowed(
+                    &self.versions,
+                    &self.version,
+                    self.follow_prereleases,
+                    self.msrv_limit.as_ref(),
+                )
+                .map(|item| item.version.clone())
+        });
+    }
+
+    /// Install a specific release on the next update, or go back to the one the policy picks
+    pub fn choose_version(&mut self, version: Option<Version>) {
+        self.chosen_version = version;
+        self.apply_policy();
+    }
+
+    /// When a release was published, from the index or the crates.io response
+    #[must_use]
+    pub fn release_date(&self, release: &IndexVersion) -> Option<String> {
+        release.published.clone().or_else(|| {
+            let version = release.version.to_string();
+
+            self.crate_response
+                .as_ref()?
+                .versions
+                .iter()
+                .find(|item| item.num == version)
+                .map(|item| item.created_at.date_naive().to_string())
+        })
+    }
+
+    /// A prerelease newer than anything the crate would update to while prereleases are not
+    /// followed
+    #[must_use]
+    pub fn newer_prerelease(&self) -> Option<&Version> {
+        if self.follow_prereleases || self.git_link.is_some() {
+            return None;
+        }
+
+        latest_prerelease(&self.versions)
+            .map(|item| &item.version)
+            .filter(|version| {
+                *version > &self.version
+                    && self
+                        .newest_version
+                        .as_ref()
+                        .is_none_or(|newest| *version > newest)
+            })
+    }
+
+    /// The release an update would install regardless of the toolchain, when the given rustc
+    /// is too old to build it
+    #[must_use]
+    pub fn msrv_blocked(&self, rustc: &Version) -> Option<&IndexVersion> {
+        if self.git_link.is_some() {
+            return None;
+        }
+
+        let candidate = if let Some(chosen) = &self.chosen_version {
+            self.versions.iter().find(|item| &item.version == chosen)
+        } else {
+            self.policy
+                .latest_allowed(&self.versions, &self.version, self.follow_prereleases, None)
+                .filter(|item| item.version > self.version)
         };
 
-        target_dir.push(".cargo");
-        target_dir.push(".crates2.json");
+        candidate.filter(|item| !item.supports(rustc))
+    }
 
-        if !target_dir.exists() {
-            println!("No crates2.json file found. Exiting");
-            std::process::exit(1);
+    /// The rustc the install was built with when it is not the given one. Yanked versions are
+    /// left out since cargo cannot install them again, as are git installs without a known
+    /// commit to build again.
+    #[must_use]
+    pub fn outdated_toolchain(&self, rustc: &Rustc) -> Option<&str> {
+        if self.is_yanked() || (self.git_link.is_some() && self.local_hash.is_none()) {
+            return None;
         }
-        let mut file = File::open(&target_dir).unwrap();
-        let mut file_content = String::new();
-        file.read_to_string(&mut file_content).unwrap();
 
-        let crate_file: CratesFile = serde_json::from_str(&file_content).unwrap();
+        let recorded = self.install_info.rustc.as_deref()?;
 
-        let mut crate_list = BTreeMap::new();
+        (!rustc.built(recorded)).then(|| recorded.lines().next().unwrap_or_default())
+    }
 
-        let config = Config::get_or_new();
+    /// The newest release when the policy keeps the crate from updating to it
+    #[must_use]
+    pub fn outside_policy(&self) -> Option<&Version> {
+        self.newest_version.as_ref().filter(|newest| {
+            *newest > &self.version && Some(*newest) != self.crates_version.as_ref()
+        })
+    }
 
-        for (name, install_info) in crate_file.installs {
-            let split_name = name.split(' ').collect::<Vec<&str>>();
-            if split_name.len() != 3 {
-                error!("Crate name {name} is not recognized. Skipping");
-                continue;
+    /// The requirement for --version, the exact target when updating, and otherwise the one
+    /// from the original install so a reinstall stays within it
+    fn version_flag(&self) -> Option<String> {
+        if self.git_link.is_some() {
+            return None;
+        }
+
+        if self.rebuild {
+            return Some(format!("={}", self.version));
+        }
+
+        if let Some(chosen) = &self.chosen_version {
+            return Some(format!("={chosen}"));
+        }
+
+        if let Some(target) = &self.crates_version {
+            // A requirement from the original install such as `1.2` would hold the update back
+            if target > &self.version {
+                return Some(format!("={target}"));
             }
 
-            let name = split_name[0];
-            let version = split_name[1];
+            if self.newest_version.is_some()
+                && (self.newest_version.as_ref() != Some(target) || !target.pre.is_empty())
+            {
+                return Some(format!("={target}"));
+            }
+        }
 
-            let source = split_name[2];
+        self.version_req.clone()
+    }
 
-            let parsed_info = parse_git_link(source);
+    /// The release an install of the crate ends up with, None for git crates
+    #[must_use]
+    pub fn install_target(&self) -> Option<&Version> {
+        if self.git_link.is_some() {
+            return None;
+        }
 
-            let Ok(version) = Version::parse(version) else {
-                error!("Failed to parse version {version} for crate {name}. Skipping");
-                continue;
-            };
+        if self.rebuild {
+            return Some(&self.version);
+        }
+
+        self.chosen_version
+            .as_ref()
+            .or(self
+                .crates_version
+                .as_ref()
+                .filter(|target| **target > self.version))
+            .or(Some(&self.version))
+    }
+
+    /// Record the newest commit of the followed reference
+    pub fn set_latest_commit(&mut self, commit: String) {
+        // A rev never moves, so it is only an update when it is not the installed commit. Cargo
+        // records the full hash while the rev may be abbreviated.
+        if let GitReference::Rev(rev) = &self.git_ref
+            && let Some(local_hash) = &self.local_hash
+            && !rev.is_empty()
+            && local_hash.to_lowercase().starts_with(&rev.to_lowercase())
+        {
+            self.latest_hash = Some(local_hash.clone());
+        } else {
+            self.latest_hash = Some(commit);
+        }
+    }
+
+    /// The newest snapshot of a build other than the installed one
+    #[must_use]
+    pub fn rollback_target(&self) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|snapshot| {
+            snapshot.version != self.version.to_string() || snapshot.hash != self.local_hash
+        })
+    }
+
+    /// Features of the version an update would install, falling back to the cached list when
+    /// the index has not been checked yet
+    #[must_use]
+    pub fn available_features(&self) -> &BTreeSet<String> {
+        self.crates_version
+            .as_ref()
+            .and_then(|target| self.versions.iter().find(|item| &item.version == target))
+            .map_or(&self.cached_features, |item| &item.features)
+    }
+
+    /// The profile from the original install, None when it was the default release profile
+    #[must_use]
+    pub fn explicit_profile(&self) -> Option<&str> {
+        self.profile
+            .as_deref()
+            .filter(|profile| *profile != "release")
+    }
+
+    /// The target from the original install, None when it was the host. Cargo records the host
+    /// even without --target, and passing it anyway keeps RUSTFLAGS from build scripts and
+    /// moves the output under `target/<triple>`.
+    #[must_use]
+    pub fn explicit_target(&self) -> Option<&str> {
+        let host = self.install_info.rustc.as_deref().and_then(host_triple);
+
+        self.target
+            .as_deref()
+            .filter(|target| Some(*target) != host)
+    }
 
-            let mut crates_version = None;
-            let mut cached_features = BTreeSet::new();
-            let mut description = "This crate has no description".to_string();
-            let mut pinned = false;
-            let mut locked = false;
+    /// The release the installed binaries came from, which a --bin subset is told apart
+    /// against. A crate that is not installed yet only has the release it is about to get.
+    #[must_use]
+    pub fn bins_release(&self) -> Option<&Version> {
+        if self.git_link.is_some() {
+            return None;
+        }
+
+        if self.version == Version::new(0, 0, 0) {
+            return self.install_target();
+        }
+
+        Some(&self.version)
+    }
 
-            let mut local_hash = None;
-            let mut git_link = None;
+    /// The flags from the original install that are not covered by the feature toggles and
+    /// have to be passed again so a reinstall ends up with the same configuration
+    #[must_use]
+    pub fn preserved_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
 
-            if let Some((link, hash)) = parsed_info {
-                local_hash = Some(hash);
-                git_link = Some(link);
+        if let Some(version_req) = self.version_flag() {
+            flags.push(String::from("--version"));
+            flags.push(version_req);
+        }
+
+        if self.all_features {
+            flags.push(String::from("--all-features"));
+        }
+
+        if let Some(profile) = self.explicit_profile() {
+            flags.push(String::from("--profile"));
+            flags.push(profile.to_string());
+        }
+
+        if let Some(target) = self.explicit_target() {
+            flags.push(String::from("--target"));
+            flags.push(target.to_string());
+        }
+
+        // Cargo records every installed binary, so only a subset picked with --bin is worth
+        // repeating. Without the binaries of the installed release that cannot be told apart.
+        if let Some((version, release_bins)) = &self.release_bins
+            && Some(version) == self.bins_release()
+            && self.bins.len() < release_bins.len()
+            && self.bins.iter().all(|bin| release_bins.contains(bin))
+        {
+            for bin in &self.bins {
+                flags.push(String::from("--bin"));
+                flags.push(bin.clone());
             }
+        }
 
-            if let Some(config) = &config
-                && let Some(crate_info) = config.crate_cache.get(name)
-            {
-                if let Some(version_string) = &crate_info.crate_version {
-                    let version = Version::parse(version_string).unwrap();
-                    crates_version = Some(version);
-                }
+        flags
+    }
+}
 
-                cached_features.clone_from(&crate_info.features);
-                description.clone_from(&crate_info.description);
-                pinned = crate_info.pinned;
-                locked = crate_info.locked;
+impl MainWindow {
+    fn new() -> Self {
+        let crate_file = match read_crates_file() {
+            Ok(crate_file) => crate_file,
+            Err(e) => {
+                println!("{e}. Exiting");
+                std::process::exit(1);
             }
+        };
 
-            let local_crate = LocalCrate {
-                name: name.to_string(),
-                description,
-                version,
-                activated_features: install_info.features.into_iter().collect(),
-                no_default_features: install_info.no_default_features,
-                crates_version,
-                crate_response: None,
-                cached_features,
-                git_link,
-                local_hash,
-                latest_hash: None,
-                pinned,
-                locked,
-            };
+        let config = Config::get_or_new();
 
-            crate_list.insert(name.to_string(), local_crate);
-        }
+        let crate_list = parse_local_crates(crate_file, config.as_ref());
 
         info!("Loaded {} crates", crate_list.len());
 
@@ -215,14 +611,111 @@ impl MainWindow {
             lerp_state: LerpState::new(0.3),
             update_crates: HashMap::new(),
             delete_crates: HashMap::new(),
-            operation_crate: None,
+            operation: None,
+            operation_crates: Vec::new(),
+            operation_done: 0,
+            stopping_operation: false,
+            operation_cancelled: false,
             logs: Vec::new(),
+            operation_logs: HashMap::new(),
+            log_filter: None,
+            history: load_history(),
+            history_filter: String::new(),
+            history_selected: None,
             git_input: GitInputState::default(),
+            policy_input: PolicyInputState::default(),
+            build_input: BuildInputState::default(),
+            review: ReviewState::default(),
+            version_picker: VersionPickerState::default(),
+            discover: DiscoverState::default(),
+            rustc: None,
+            toolchains: HashMap::new(),
+            build_cache_size: None,
+            sccache: false,
+            manifest: ManifestState::default(),
             config,
             update_available: None,
+            crates_file_modified: crates_file_modified(),
+        }
+    }
+
+    /// The toolchain a crate is built with from its build settings or the defaults, None for
+    /// the default one
+    fn toolchain_of<'a>(&'a self, item: &'a LocalCrate) -> Option<&'a str> {
+        item.build.toolchain.as_deref().or_else(|| {
+            self.config
+                .as_ref()
+                .and_then(|config| config.build_defaults.toolchain.as_deref())
+        })
+    }
+
+    /// The rustc that builds a crate, once it is known
+    fn rustc_for(&self, item: &LocalCrate) -> Option<&Rustc> {
+        match self.toolchain_of(item) {
+            Some(toolchain) => self.toolchains.get(toolchain),
+            None => self.rustc.as_ref(),
         }
     }
 
+    /// Look up the rustc of every toolchain the build settings name that is not known yet
+    fn fetch_toolchains(&self) -> Task<Message> {
+        let Some(mut worker) = self.worker.clone() else {
+            return Task::none();
+        };
+
+        let toolchains = self
+            .crate_list
+            .values()
+            .filter_map(|item| self.toolchain_of(item))
+            .filter(|toolchain| !self.toolchains.contains_key(*toolchain))
+            .map(String::from)
+            .collect::<BTreeSet<String>>();
+
+        if toolchains.is_empty() {
+            return Task::none();
+        }
+
+        Task::perform(
+            async move {
+                for toolchain in toolchains {
+                    let _ = worker
+                        .send(WorkerInput::GetRustcVersion(Some(toolchain)))
+                        .await;
+                }
+            },
+            |()| Message::None,
+        )
+    }
+
+    /// The toolchain update targets of a crate have to support, when falling back to
+    /// compatible releases
+    fn msrv_limit(&self, item: &LocalCrate) -> Option<Version> {
+        self.rustc_for(item)
+            .map(|rustc| rustc.version.clone())
+            .filter(|_| self.config.as_ref().is_some_and(|c| c.msrv_fallback))
+    }
+
+    /// Pick the update targets again after a toolchain or the fallback setting changed
+    fn apply_msrv_limit(&mut self) {
+        let limits = self
+            .crate_list
+            .values()
+            .map(|item| self.msrv_limit(item))
+            .collect::<Vec<Option<Version>>>();
+
+        for (item, msrv_limit) in self.crate_list.values_mut().zip(limits) {
+            item.msrv_limit = msrv_limit;
+            item.apply_policy();
+        }
+    }
+
+    fn install_defaults(&self) -> InstallDefaults {
+        self.config
+            .as_ref()
+            .map(Config::install_defaults)
+            .unwrap_or_default()
+    }
+
     fn title(&self) -> String {
         "Crane".to_string()
     }
@@ -236,6 +729,12 @@ impl MainWindow {
             Page::Logs => {
                 to_render = to_render.push(self.log_page());
             }
+            Page::History => {
+                to_render = to_render.push(self.history_page());
+            }
+            Page::Discover => {
+                to_render = to_render.push(self.discover_page());
+            }
         }
 
         let container_height = self
@@ -269,6 +768,71 @@ impl MainWindow {
             );
         }
 
+        let policy_modal_width = self
+            .lerp_state
+            .get(POLICY_MODAL_WIDTH_KEY)
+            .unwrap_or_default();
+
+        if self.policy_input.show_modal || policy_modal_width > 0.0 {
+            return modal(
+                to_render,
+                self.policy_modal(),
+                Message::Policy(PolicyEvent::Hide),
+            );
+        }
+
+        let review_modal_length = self
+            .lerp_state
+            .get(REVIEW_MODAL_LENGTH_KEY)
+            .unwrap_or_default();
+
+        if self.review.show_modal || review_modal_length > 0.0 {
+            return modal(
+                to_render,
+                self.review_modal(),
+                Message::Review(ReviewEvent::Hide),
+            );
+        }
+
+        let build_modal_width = self
+            .lerp_state
+            .get(BUILD_MODAL_WIDTH_KEY)
+            .unwrap_or_default();
+
+        if self.build_input.show_modal || build_modal_width > 0.0 {
+            return modal(
+                to_render,
+                self.build_modal(),
+                Message::Build(BuildEvent::Hide),
+            );
+        }
+
+        let version_modal_length = self
+            .lerp_state
+            .get(VERSION_MODAL_LENGTH_KEY)
+            .unwrap_or_default();
+
+        if self.version_picker.show_modal || version_modal_length > 0.0 {
+            return modal(
+                to_render,
+                self.version_modal(),
+                Message::VersionPicker(VersionPickerEvent::Hide),
+            );
+        }
+
+        let manifest_modal_length = self
+            .lerp_state
+            .get(MANIFEST_MODAL_LENGTH_KEY)
+            .unwrap_or_default();
+
+        if self.manifest.show_modal || manifest_modal_length > 0.0 {
+            return modal(
+                to_render,
+                self.manifest_modal(),
+                Message::Manifest(ManifestEvent::Hide),
+            );
+        }
+
         if self.update_available.is_some() || update_modal_length > 0.0 {
             return modal(to_render, self.update_modal(), Message::CloseUpdateModal);
         }
@@ -279,6 +843,7 @@ impl MainWindow {
     fn subscription(&self) -> Subscription<Message> {
         Subscription::batch([
             Subscription::run(event_worker).map(Message::FetchEvent),
+            Subscription::run(watch_crates_file).map(|()| Message::CheckCratesFile),
             if self.lerp_state.has_active_lerps() {
                 time::every(Duration::from_millis(16)).map(|_| Message::Tick)
             } else {
@@ -290,6 +855,14 @@ impl MainWindow {
     fn update_lerp_states_operation_container(&mut self) {
         if self.update_crates.is_empty() && self.delete_crates.is_empty() {
             self.lerp_state.lerp(OPERATION_CONTAINER_KEY, 0.0);
+        } else if self.operation == Some(OperationType::Update) {
+            // Room for the build progress of every crate being installed
+            let builds = self.operation_crates.len().max(1) as f64;
+
+            self.lerp_state.lerp(
+                OPERATION_CONTAINER_KEY,
+                OPERATION_CONTAINER + BUILD_PROGRESS_HEIGHT * builds,
+            );
         } else {
             self.lerp_state
                 .lerp(OPERATION_CONTAINER_KEY, OPERATION_CONTAINER);
@@ -308,18 +881,454 @@ impl MainWindow {
     fn update_lerp_states_operation_progress(&mut self) {
         let total_operation = self.update_crates.len() + self.delete_crates.len();
 
-        let Some(ongoing_operation) = self.operation_crate.as_ref() else {
+        if self.operation.is_none() || total_operation == 0 {
             return;
+        }
+
+        let progress_status = (self.operation_done as f64 / total_operation as f64) * 100.0;
+
+        self.lerp_state
+            .lerp(OPERATION_PROGRESS_KEY, progress_status);
+    }
+
+    fn crate_version_input(&self, crates: Vec<String>) -> WorkerInput {
+        WorkerInput::crate_version(crates, self.config.as_ref())
+    }
+
+    /// The staged installs in the order they start, using the current state of the crates so
+    /// toggles changed after staging apply. Imported crates keep the options of the manifest.
+    fn staged_updates(&self) -> Vec<LocalCrate> {
+        let mut crate_list = self
+            .update_crates
+            .iter()
+            .map(|(name, staged)| {
+                let mut item = if self.manifest.pending.contains_key(name) {
+                    staged.clone()
+                } else {
+                    self.crate_list.get(name).unwrap_or(staged).clone()
+                };
+                item.force = staged.force;
+                item.rebuild = staged.rebuild;
+                item.command.clone_from(&staged.command);
+                item
+            })
+            .collect::<Vec<LocalCrate>>();
+
+        crate_list.sort_by(|a, b| a.name.cmp(&b.name));
+
+        crate_list
+    }
+
+    /// The staged removals in the order they run
+    fn staged_deletes(&self) -> Vec<LocalCrate> {
+        let mut crate_list = self
+            .delete_crates
+            .values()
+            .cloned()
+            .collect::<Vec<LocalCrate>>();
+
+        crate_list.sort_by(|a, b| a.name.cmp(&b.name));
+
+        crate_list
+    }
+
+    /// Look up the binaries of the installed release of every staged crate that are not known
+    /// yet, so a --bin subset from the original install can be repeated
+    fn fetch_release_bins(&self) -> Task<Message> {
+        let Some(mut worker) = self.worker.clone() else {
+            return Task::none();
         };
 
-        let currently_at = match ongoing_operation.operation_type {
-            OperationType::Update => ongoing_operation.index,
-            OperationType::Delete => self.update_crates.len() + ongoing_operation.index,
+        let releases = self
+            .staged_updates()
+            .iter()
+            .filter_map(|item| {
+                let release = item.bins_release()?;
+                let known = item.release_bins.as_ref().map(|(version, _)| version);
+
+                (known != Some(release)).then(|| (item.name.clone(), release.clone()))
+            })
+            .collect::<Vec<(String, Version)>>();
+
+        if releases.is_empty() {
+            return Task::none();
+        }
+
+        let input = WorkerInput::release_bins(releases, self.config.as_ref());
+
+        Task::perform(
+            async move {
+                let _ = worker.send(input).await;
+            },
+            |()| Message::None,
+        )
+    }
+
+    /// Build the review again, keeping the commands that were edited or removed
+    fn refresh_review(&mut self) {
+        let previous = std::mem::take(&mut self.review.commands);
+
+        self.review.commands = self
+            .review_commands()
+            .into_iter()
+            .filter_map(|command| {
+                let old = previous.iter().find(|old| {
+                    old.crate_name == command.crate_name && old.operation == command.operation
+                })?;
+
+                Some(if old.edited() { old.clone() } else { command })
+            })
+            .collect();
+    }
+
+    /// Every command the staged operation runs, updates first since removals wait for them
+    fn review_commands(&self) -> Vec<ReviewCommand> {
+        let defaults = self.install_defaults();
+
+        let updates = self.staged_updates().into_iter().map(|item| {
+            let command = install_command(&item, &defaults);
+            ReviewCommand::new(item.name, OperationType::Update, &command)
+        });
+
+        let deletes = self.staged_deletes().into_iter().map(|item| {
+            let command = uninstall_command(&item.name);
+            ReviewCommand::new(item.name, OperationType::Delete, &command)
+        });
+
+        updates.chain(deletes).collect()
+    }
+
+    /// Start the staged updates, the removals follow once they are done
+    fn apply_operation(&self) -> Task<Message> {
+        let Some(mut worker) = self.worker.clone() else {
+            return Task::none();
+        };
+
+        if !self.update_crates.is_empty() {
+            let crate_list = self.staged_updates();
+            let defaults = self.install_defaults();
+
+            Task::perform(
+                async move {
+                    let _ = worker
+                        .send(WorkerInput::UpdateCrates(crate_list, defaults))
+                        .await;
+                },
+                |()| Message::None,
+            )
+        } else if !self.delete_crates.is_empty() {
+            let crate_list = self.staged_deletes();
+
+            Task::perform(
+                async move {
+                    let _ = worker.send(WorkerInput::DeleteCrates(crate_list)).await;
+                },
+                |()| Message::None,
+            )
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Measure the shared target directory again
+    fn refresh_build_cache(&self) -> Task<Message> {
+        let Some(mut worker) = self.worker.clone() else {
+            return Task::none();
         };
 
-        let progress_status = (currently_at as f64 / total_operation as f64) * 100.0;
+        Task::perform(
+            async move {
+                let _ = worker.send(WorkerInput::GetBuildCache).await;
+            },
+            |()| Message::None,
+        )
+    }
+
+    /// A scan of every installed crate against the advisory database
+    fn advisory_scan_input(&self) -> Option<WorkerInput> {
+        let db = match &self.config {
+            Some(config) => config.advisory_db_path(),
+            None => advisory::default_db_path(),
+        }?;
+
+        let bin_dir = cargo_home()?.join("bin");
+
+        let targets = self
+            .crate_list
+            .values()
+            .map(|item| ScanTarget {
+                name: item.name.clone(),
+                version: item.version.clone(),
+                bins: item
+                    .install_info
+                    .bins
+                    .iter()
+                    .map(|bin| bin_dir.join(bin))
+                    .collect(),
+            })
+            .collect();
+
+        Some(WorkerInput::ScanAdvisories(db, targets))
+    }
+
+    fn advance_fetch_progress(&mut self) {
+        self.fetch_progress = Some(self.fetch_progress.unwrap_or(0) + 1);
+
+        let mut progress_status = 0.0;
+        let total_item = self.crate_list.len();
+
+        if let Some(progress) = self.fetch_progress {
+            progress_status = (progress as f32 / total_item as f32) * 100.0;
+        }
 
         self.lerp_state
-            .lerp(OPERATION_PROGRESS_KEY, progress_status);
+            .lerp(FETCH_PROGRESS_KEY, f64::from(progress_status));
+
+        self.lerp_state
+            .lerp(FETCH_PROGRESS_HEIGHT_KEY, FETCH_PROGRESS_HEIGHT);
+    }
+
+    /// Drop every hand picked release once nothing is staged anymore
+    fn clear_chosen_versions(&mut self) {
+        for item in self.crate_list.values_mut() {
+            if item.chosen_version.is_some() {
+                item.choose_version(None);
+            }
+        }
+    }
+
+    pub fn push_log(&mut self, log: String) {
+        self.logs.push(log);
+
+        if self.logs.len() > 1000 {
+            self.logs.remove(0);
+        }
+    }
+
+    /// Stage everything needed to make the installed crates match a manifest. The installed
+    /// crates are left alone until the operation is applied, settings that only Crane uses are
+    /// saved right away when nothing has to be installed or removed.
+    fn stage_manifest(&mut self, manifest: &Manifest) {
+        let diff = manifest.diff(&self.crate_list);
+        let mut lines = Vec::new();
+
+        for item in diff.install {
+            lines.push(format!("Install {}", item.name));
+
+            self.manifest
+                .pending
+                .insert(item.name.clone(), (item.pinned, item.locked));
+            self.delete_crates.remove(&item.name);
+            self.update_crates.insert(item.name.clone(), item);
+        }
+
+        for change in diff.change {
+            let Some(mut target_crate) = self.crate_list.get(&change.name).cloned() else {
+                continue;
+            };
+
+            change.entry.apply_to(&mut target_crate);
+
+            self.manifest.pending.insert(
+                change.name.clone(),
+                (change.entry.pinned, change.entry.locked),
+            );
+
+            if change.reinstall {
+                self.delete_crates.remove(&change.name);
+                self.update_crates.insert(change.name.clone(), target_crate);
+            }
+
+            lines.push(format!(
+                "Change {}: {}",
+                change.name,
+                change.changes.join(", ")
+            ));
+        }
+
+        for name in diff.remove {
+            lines.push(format!("Remove {name}"));
+
+            if let Some(item) = self.crate_list.get(&name) {
+                self.update_crates.remove(&name);
+                self.delete_crates.insert(name, item.clone());
+            }
+        }
+
+        if lines.is_empty() {
+            lines.push(String::from(
+                "The installed crates already match the manifest",
+            ));
+        }
+
+        for line in &lines {
+            info!("{line}");
+            self.push_log(line.clone());
+        }
+
+        self.manifest.status = lines;
+
+        if self.update_crates.is_empty() && self.delete_crates.is_empty() {
+            self.commit_manifest();
+        }
+
+        self.update_lerp_states_operation_container();
+    }
+
+    /// Save the pin and `--locked` setting of the imported crates
+    fn commit_manifest(&mut self) {
+        for (name, (pinned, locked)) in std::mem::take(&mut self.manifest.pending) {
+            if let Some(target_crate) = self.crate_list.get_mut(&name) {
+                target_crate.pinned = pinned;
+                target_crate.locked = locked;
+            }
+
+            if let Some(config) = &mut self.config {
+                config.update_pinned(name.clone(), pinned);
+                config.update_locked(name, locked);
+            }
+        }
+    }
+
+    /// Reparse crates2.json and bring the crate list in line with it, logging every
+    /// difference. Crates that were not known before get their details fetched.
+    fn reconcile_crates(&mut self) -> Task<Message> {
+        self.crates_file_modified = crates_file_modified();
+
+        let crate_file = match read_crates_file() {
+            Ok(crate_file) => crate_file,
+            Err(e) => {
+                error!("{e}");
+                return Task::none();
+            }
+        };
+
+        let mut new_list = parse_local_crates(crate_file, self.config.as_ref());
+
+        let mut changes = Vec::new();
+        let mut new_crates = Vec::new();
+        let mut new_git_crates = HashMap::new();
+
+        for (name, old_crate) in &self.crate_list {
+            if !new_list.contains_key(name) {
+                changes.push(format!("Removed {name} v{}", old_crate.version));
+            }
+        }
+
+        for (name, new_crate) in &mut new_list {
+            new_crate.msrv_limit = self.msrv_limit(new_crate);
+
+            let Some(old_crate) = self.crate_list.get(name) else {
+                changes.push(format!("Added {name} v{}", new_crate.version));
+
+                if let Some(git_source) = new_crate.git_source() {
+                    new_git_crates.insert(name.clone(), git_source);
+                } else {
+                    new_crates.push(name.clone());
+                }
+                continue;
+            };
+
+            if old_crate.version != new_crate.version {
+                changes.push(format!(
+                    "Changed {name} v{} → v{}",
+                    old_crate.version, new_crate.version
+                ));
+            } else if old_crate.local_hash != new_crate.local_hash {
+                changes.push(format!(
+                    "Changed {name} {} → {}",
+                    old_crate.local_hash.as_deref().unwrap_or("crates.io"),
+                    new_crate.local_hash.as_deref().unwrap_or("crates.io")
+                ));
+            } else if old_crate.install_info != new_crate.install_info {
+                changes.push(format!("Changed install options of {name}"));
+            }
+
+            // Nothing changed on disk, keep everything including unapplied toggles
+            if old_crate.version == new_crate.version
+                && old_crate.local_hash == new_crate.local_hash
+                && old_crate.install_info == new_crate.install_info
+            {
+                let snapshots = std::mem::take(&mut new_crate.snapshots);
+
+                *new_crate = old_crate.clone();
+                new_crate.snapshots = snapshots;
+                continue;
+            }
+
+            // Keep what was fetched or set in this session
+            new_crate.description.clone_from(&old_crate.description);
+            new_crate
+                .crates_version
+                .clone_from(&old_crate.crates_version);
+            new_crate
+                .crate_response
+                .clone_from(&old_crate.crate_response);
+            new_crate.versions.clone_from(&old_crate.versions);
+            new_crate.apply_policy();
+            new_crate.latest_hash.clone_from(&old_crate.latest_hash);
+            new_crate.failure.clone_from(&old_crate.failure);
+
+            if new_crate.git_link.is_none() {
+                new_crate.git_link.clone_from(&old_crate.git_link);
+                new_crate.git_ref.clone_from(&old_crate.git_ref);
+            }
+        }
+
+        let changed = !changes.is_empty();
+
+        for change in changes {
+            info!("{change}");
+            self.push_log(change);
+        }
+
+        // Staged installs of crates that were never installed are kept
+        self.update_crates
+            .retain(|name, _| new_list.contains_key(name) || !self.crate_list.contains_key(name));
+
+        self.crate_list = new_list;
+
+        self.delete_crates
+            .retain(|name, _| self.crate_list.contains_key(name));
+
+        let Some(mut worker) = self.worker.clone() else {
+            return Task::none();
+        };
+
+        // Versions moved, so earlier findings may no longer apply
+        let scan_input = changed.then(|| self.advisory_scan_input()).flatten();
+
+        if new_crates.is_empty() && new_git_crates.is_empty() && scan_input.is_none() {
+            return Task::none();
+        }
+
+        // Only track the fetch on the load bar when a crates.io check is going to finish it
+        let load_bar = !new_crates.is_empty();
+
+        if load_bar {
+            self.fetch_progress =
+                Some(self.crate_list.len() - new_crates.len() - new_git_crates.len());
+        }
+
+        let version_input = (!new_crates.is_empty()).then(|| self.crate_version_input(new_crates));
+
+        Task::perform(
+            async move {
+                if let Some(version_input) = version_input {
+                    let _ = worker.send(version_input).await;
+                }
+
+                if !new_git_crates.is_empty() {
+                    let _ = worker
+                        .send(WorkerInput::GetGitCommit(new_git_crates, load_bar))
+                        .await;
+                }
+
+                if let Some(scan_input) = scan_input {
+                    let _ = worker.send(scan_input).await;
+                }
+            },
+            |()| Message::None,
+        )
     }
 }
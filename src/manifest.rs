@@ -0,0 +1,336 @@
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{Read as _, Write as _};
+
+use crate::LocalCrate;
+use crate::crates_file::InstallInfo;
+use crate::git::GitReference;
+use crate::utils::expand_path;
+
+/// A shareable description of a set of installed crates and how each was installed
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub crates: BTreeMap<String, ManifestCrate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManifestCrate {
+    /// The version requirement passed to --version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub features: BTreeSet<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_default_features: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub all_features: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bins: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// None follows the global default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked: Option<bool>,
+}
+
+/// What applying a manifest would do to the installed crates
+#[derive(Debug, Default)]
+pub struct ManifestDiff {
+    /// Crates in the manifest that are not installed
+    pub install: Vec<LocalCrate>,
+    /// Installed crates whose options differ
+    pub change: Vec<ManifestChange>,
+    /// Installed crates missing from the manifest
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ManifestChange {
+    pub name: String,
+    pub entry: ManifestCrate,
+    /// A line for each difference
+    pub changes: Vec<String>,
+    /// Whether the crate has to be installed again for the changes to apply
+    pub reinstall: bool,
+}
+
+/// The manifest location offered by default, in the home directory
+#[must_use]
+pub fn default_manifest_path() -> String {
+    home_dir()
+        .map(|mut path| {
+            path.push("crane.toml");
+            path.to_string_lossy().to_string()
+        })
+        .unwrap_or_else(|| String::from("crane.toml"))
+}
+
+impl Manifest {
+    #[must_use]
+    pub fn from_crates<'a>(crates: impl Iterator<Item = &'a LocalCrate>) -> Self {
+        let crates = crates
+            .map(|item| (item.name.clone(), ManifestCrate::from_crate(item)))
+            .collect();
+
+        Self { crates }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut buf = String::new();
+
+        File::open(expand_path(path))
+            .and_then(|mut file| file.read_to_string(&mut buf))
+            .map_err(|e| format!("Failed to read {path}: {e}"))?;
+
+        toml::from_str(&buf).map_err(|e| format!("Failed to parse {path}: {e}"))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize manifest: {e}"))?;
+
+        File::create(expand_path(path))
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| format!("Failed to write {path}: {e}"))
+    }
+
+    /// Compare the manifest with the installed crates
+    #[must_use]
+    pub fn diff(&self, installed: &BTreeMap<String, LocalCrate>) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+
+        for (name, entry) in &self.crates {
+            match installed.get(name) {
+                Some(item) => {
+                    let mut changes = entry.differences(item);
+                    let reinstall = !changes.is_empty();
+
+                    changes.extend(entry.setting_differences(item));
+
+                    if !changes.is_empty() {
+                        diff.change.push(ManifestChange {
+                            name: name.clone(),
+                            entry: entry.clone(),
+                            changes,
+                            reinstall,
+                        });
+                    }
+                }
+                None => diff.install.push(entry.new_crate(name)),
+            }
+        }
+
+        diff.remove = installed
+            .keys()
+            .filter(|name| !self.crates.contains_key(*name))
+            .cloned()
+            .collect();
+
+        diff
+    }
+}
+
+impl ManifestCrate {
+    fn from_crate(item: &LocalCrate) -> Self {
+        let (branch, tag, rev) = match &item.git_ref {
+            GitReference::DefaultBranch => (None, None, None),
+            GitReference::Branch(name) => (Some(name.clone()), None, None),
+            GitReference::Tag(name) => (None, Some(name.clone()), None),
+            GitReference::Rev(name) => (None, None, Some(name.clone())),
+        };
+
+        Self {
+            version: item.version_req.clone(),
+            git: item.git_link.clone(),
+            branch,
+            tag,
+            rev,
+            features: item.activated_features.iter().cloned().collect(),
+            no_default_features: item.no_default_features,
+            all_features: item.all_features,
+            // The host target and the release profile are the defaults on every machine, so
+            // they are left out to keep the manifest portable
+            profile: item.explicit_profile().map(String::from),
+            target: item.explicit_target().map(String::from),
+            bins: item.bins.clone(),
+            pinned: item.pinned,
+            locked: item.locked,
+        }
+    }
+
+    #[must_use]
+    pub fn git_ref(&self) -> GitReference {
+        if let Some(branch) = &self.branch {
+            GitReference::Branch(branch.clone())
+        } else if let Some(tag) = &self.tag {
+            GitReference::Tag(tag.clone())
+        } else if let Some(rev) = &self.rev {
+            GitReference::Rev(rev.clone())
+        } else {
+            GitReference::DefaultBranch
+        }
+    }
+
+    /// Differences that need a reinstall to take effect, described for the user
+    fn differences(&self, item: &LocalCrate) -> Vec<String> {
+        let current = ManifestCrate::from_crate(item);
+        let mut changes = Vec::new();
+
+        let describe = |value: Option<&String>| value.map_or("none", String::as_str).to_string();
+
+        if current.git != self.git || current.git_ref() != self.git_ref() {
+            let source = |entry: &ManifestCrate| match &entry.git {
+                Some(git) => format!("{git} ({})", entry.git_ref()),
+                None => String::from("crates.io"),
+            };
+
+            changes.push(format!("source {} → {}", source(&current), source(self)));
+        }
+
+        if current.version != self.version {
+            changes.push(format!(
+                "version {} → {}",
+                describe(current.version.as_ref()),
+                describe(self.version.as_ref())
+            ));
+        }
+
+        if current.features != self.features {
+            let join = |features: &BTreeSet<String>| {
+                if features.is_empty() {
+                    String::from("none")
+                } else {
+                    features.iter().cloned().collect::<Vec<String>>().join(", ")
+                }
+            };
+
+            changes.push(format!(
+                "features {} → {}",
+                join(&current.features),
+                join(&self.features)
+            ));
+        }
+
+        if current.no_default_features != self.no_default_features {
+            changes.push(format!(
+                "default features {}",
+                if self.no_default_features {
+                    "off"
+                } else {
+                    "on"
+                }
+            ));
+        }
+
+        if current.all_features != self.all_features {
+            changes.push(format!(
+                "all features {}",
+                if self.all_features { "on" } else { "off" }
+            ));
+        }
+
+        if current.profile != self.profile {
+            changes.push(format!(
+                "profile {} → {}",
+                describe(current.profile.as_ref()),
+                describe(self.profile.as_ref())
+            ));
+        }
+
+        if current.target != self.target {
+            changes.push(format!(
+                "target {} → {}",
+                describe(current.target.as_ref()),
+                describe(self.target.as_ref())
+            ));
+        }
+
+        if current.bins != self.bins {
+            changes.push(format!(
+                "bins {} → {}",
+                current.bins.join(", "),
+                self.bins.join(", ")
+            ));
+        }
+
+        changes
+    }
+
+    /// Differences in settings that only Crane uses and apply right away
+    fn setting_differences(&self, item: &LocalCrate) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        if item.pinned != self.pinned {
+            changes.push(String::from(if self.pinned {
+                "pinned"
+            } else {
+                "unpinned"
+            }));
+        }
+
+        if item.locked != self.locked {
+            changes.push(String::from(match self.locked {
+                Some(true) => "--locked on",
+                Some(false) => "--locked off",
+                None => "--locked follows the default",
+            }));
+        }
+
+        changes
+    }
+
+    /// Set the install options of an installed crate to the ones in the manifest
+    pub fn apply_to(&self, item: &mut LocalCrate) {
+        if item.git_link != self.git || item.git_ref != self.git_ref() {
+            item.latest_hash = None;
+        }
+
+        item.git_link.clone_from(&self.git);
+        item.git_ref = self.git_ref();
+        item.version_req.clone_from(&self.version);
+        item.activated_features = self.features.iter().cloned().collect();
+        item.no_default_features = self.no_default_features;
+        item.all_features = self.all_features;
+        item.profile.clone_from(&self.profile);
+        item.target.clone_from(&self.target);
+        item.bins.clone_from(&self.bins);
+        item.pinned = self.pinned;
+        item.locked = self.locked;
+    }
+
+    /// A crate that is not installed yet, staged so it can go through the usual install
+    fn new_crate(&self, name: &str) -> LocalCrate {
+        let mut item = LocalCrate::not_installed(name);
+
+        item.install_info = InstallInfo {
+            version_req: self.version.clone(),
+            bins: self.bins.clone(),
+            features: self.features.iter().cloned().collect(),
+            all_features: self.all_features,
+            no_default_features: self.no_default_features,
+            profile: self.profile.clone(),
+            target: self.target.clone(),
+            rustc: None,
+        };
+
+        self.apply_to(&mut item);
+
+        item
+    }
+}
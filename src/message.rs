@@ -2,27 +2,222 @@ use iced::Task;
 use iced::futures::SinkExt;
 use log::{error, info};
 use semver::Version;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
+use crate::build_cache::format_size;
+use crate::build_settings::{
+    BuildSettings, join_words, mask_secrets, parse_env, split_env, split_words,
+};
 use crate::components::{
-    FETCH_PROGRESS_HEIGHT, FETCH_PROGRESS_HEIGHT_KEY, FETCH_PROGRESS_KEY, GIT_MODAL_WIDTH,
-    GIT_MODAL_WIDTH_KEY,
+    BUILD_MODAL_WIDTH, BUILD_MODAL_WIDTH_KEY, GIT_MODAL_WIDTH, GIT_MODAL_WIDTH_KEY,
+    MANIFEST_MODAL_LENGTH, MANIFEST_MODAL_LENGTH_KEY, POLICY_MODAL_WIDTH, POLICY_MODAL_WIDTH_KEY,
+    REVIEW_MODAL_LENGTH, REVIEW_MODAL_LENGTH_KEY, VERSION_MODAL_LENGTH, VERSION_MODAL_LENGTH_KEY,
+    build_progress_key,
 };
+use crate::crates_file::crates_file_modified;
+use crate::discover::{CrateDetails, SearchResult};
+use crate::git::{GitReference, GitReferenceKind};
+use crate::history::HISTORY_LIMIT;
+use crate::index::latest_release;
+use crate::manifest::{Manifest, default_manifest_path};
+use crate::policy::{UpdatePolicy, UpdatePolicyKind};
 use crate::worker::{WorkerEvent, WorkerInput};
-use crate::{MainWindow, OperationCrate, OperationType, Page};
+use crate::{CrateFailure, LocalCrate, MainWindow, OperationCrate, OperationType, Page};
 
 #[derive(Debug, Clone, Default)]
 pub struct GitInputState {
     pub modal_text: String,
     pub show_modal: bool,
+    pub reference_kind: GitReferenceKind,
+    pub reference_text: String,
+    crate_name: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ManifestState {
+    pub show_modal: bool,
+    pub path: String,
+    /// What the last export or import did
+    pub status: Vec<String>,
+    /// The pin and `--locked` setting of every imported crate, saved once the staged operation
+    /// is applied
+    pub pending: HashMap<String, (bool, Option<bool>)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PolicyInputState {
+    pub show_modal: bool,
+    pub kind: UpdatePolicyKind,
+    pub text: String,
+    /// Why the last submit was rejected
+    pub error: Option<String>,
+    crate_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum PolicyEvent {
+    Show(String),
+    Hide,
+    Kind(UpdatePolicyKind),
+    Input(String),
+    Submit,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildInputState {
+    pub show_modal: bool,
+    pub toolchain: String,
+    pub env: String,
+    pub args: String,
+    /// Why the last submit was rejected
+    pub error: Option<String>,
     crate_name: String,
 }
 
+#[derive(Debug, Clone)]
+pub enum BuildEvent {
+    Show(String),
+    Hide,
+    Toolchain(String),
+    Env(String),
+    Args(String),
+    Submit,
+}
+
+/// A command the staged operation is about to run, as shown in the review
+#[derive(Debug, Clone)]
+pub struct ReviewCommand {
+    pub crate_name: String,
+    pub operation: OperationType,
+    /// The command Crane would run without edits
+    pub generated: String,
+    pub text: String,
+}
+
+impl ReviewCommand {
+    #[must_use]
+    pub fn new(crate_name: String, operation: OperationType, generated: &[String]) -> Self {
+        let generated = join_words(generated);
+
+        Self {
+            crate_name,
+            operation,
+            text: generated.clone(),
+            generated,
+        }
+    }
+
+    #[must_use]
+    pub fn edited(&self) -> bool {
+        self.text.trim() != self.generated
+    }
+
+    /// The edited command, or None when it was left as generated
+    pub fn parse(&self) -> Result<Option<Vec<String>>, String> {
+        if !self.edited() {
+            return Ok(None);
+        }
+
+        let words = split_words(&self.text)?;
+
+        if split_env(&words).1.is_empty() {
+            return Err(String::from("the command is empty"));
+        }
+
+        Ok(Some(words))
+    }
+
+    /// The command as it would be logged, with credential values hidden. A command that no
+    /// longer parses shows the generated one instead of the raw text.
+    #[must_use]
+    pub fn masked(&self) -> String {
+        let words = split_words(&self.text)
+            .or_else(|_| split_words(&self.generated))
+            .unwrap_or_default();
+
+        join_words(&mask_secrets(&words))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReviewState {
+    pub show_modal: bool,
+    /// Updates first, then removals, in the order they start
+    pub commands: Vec<ReviewCommand>,
+    /// Why the last confirm was rejected
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReviewEvent {
+    Show,
+    Hide,
+    Input(usize, String),
+    Remove(usize),
+    DryRun,
+    Confirm,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiscoverState {
+    pub query: String,
+    pub searching: bool,
+    pub results: Vec<SearchResult>,
+    /// Why the last search failed or found nothing
+    pub status: Option<String>,
+    /// The result whose install options are open
+    pub selected: Option<String>,
+    /// Versions and binaries of every result that was opened
+    pub details: HashMap<String, Result<CrateDetails, String>>,
+    pub activated_features: HashSet<String>,
+    pub no_default_features: bool,
+    pub git_link: String,
+    pub reference_kind: GitReferenceKind,
+    pub reference_text: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum DiscoverEvent {
+    Input(String),
+    Search,
+    Select(String),
+    FeatureToggle(String),
+    GitInput(String),
+    ReferenceKind(GitReferenceKind),
+    ReferenceInput(String),
+    Install,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VersionPickerState {
+    pub show_modal: bool,
+    pub crate_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum VersionPickerEvent {
+    Show(String),
+    Hide,
+    Select(Version),
+}
+
+#[derive(Debug, Clone)]
+pub enum ManifestEvent {
+    Show,
+    Hide,
+    Input(String),
+    Export,
+    Import,
+}
+
 #[derive(Debug, Clone)]
 pub enum GitInputEvent {
     HideModal,
     Submit,
     Input(String),
+    ReferenceKind(GitReferenceKind),
+    ReferenceInput(String),
 }
 
 #[derive(Debug, Clone)]
@@ -34,10 +229,17 @@ pub enum Message {
     HoveringExit(usize),
     Tick,
     CancelOperation,
-    ApplyOperation,
+    StopOperation,
     ShowLog,
+    ShowCrateLog(String),
     ShowCrates,
+    ShowHistory,
+    ShowDiscover,
+    Discover(DiscoverEvent),
+    HistoryFilter(String),
+    SelectHistory(usize),
     UpdateAll,
+    FixVulnerable,
     FeatureToggle {
         crate_name: String,
         feature_name: String,
@@ -45,11 +247,25 @@ pub enum Message {
     ToggleGitLink {
         crate_name: String,
     },
+    Rollback(String),
     TogglePin(String),
+    ToggleKeepYanked(String),
+    TogglePrereleases(String),
     ToggleLocked(String),
+    ToggleAlwaysLocked,
+    ToggleMsrvFallback,
+    RebuildOutdated,
+    ToggleSharedTarget,
+    CleanBuildCache,
     GitInput(GitInputEvent),
+    Policy(PolicyEvent),
+    Build(BuildEvent),
+    Review(ReviewEvent),
+    VersionPicker(VersionPickerEvent),
+    Manifest(ManifestEvent),
     CloseUpdateModal,
     OpenUpdateLink,
+    CheckCratesFile,
     None,
 }
 
@@ -61,6 +277,10 @@ impl MainWindow {
 
                 if self.update_crates.contains_key(&crate_name) {
                     self.update_crates.remove(&crate_name);
+
+                    if let Some(target_crate) = self.crate_list.get_mut(&crate_name) {
+                        target_crate.choose_version(None);
+                    }
                 } else {
                     self.update_crates.insert(crate_name.clone(), target_crate);
                 }
@@ -83,143 +303,229 @@ impl MainWindow {
                     self.worker = Some(sender.clone());
                     let mut crate_names = Vec::new();
 
-                    let rate_limit = self.config.as_ref().map_or(1000, |c| c.crate_rate_limit_ms);
-
                     let mut git_crate_list = HashMap::new();
 
                     for crate_details in self.crate_list.values() {
-                        if let Some(git_url) = &crate_details.git_link {
-                            git_crate_list.insert(crate_details.name.clone(), git_url.clone());
+                        if let Some(git_source) = crate_details.git_source() {
+                            git_crate_list.insert(crate_details.name.clone(), git_source);
                         } else {
                             crate_names.push(crate_details.name.clone());
                         }
                     }
 
-                    return Task::perform(
+                    let version_input = self.crate_version_input(crate_names);
+                    let scan_input = self.advisory_scan_input();
+
+                    let startup = Task::perform(
                         async move {
-                            let _ = sender
-                                .send(WorkerInput::GetCrateVersion(crate_names, rate_limit))
-                                .await;
+                            let _ = sender.send(WorkerInput::GetRustcVersion(None)).await;
+                            let _ = sender.send(WorkerInput::GetBuildCache).await;
+                            let _ = sender.send(version_input).await;
 
                             let _ = sender
                                 .send(WorkerInput::GetGitCommit(git_crate_list, true))
                                 .await;
 
+                            if let Some(scan_input) = scan_input {
+                                let _ = sender.send(scan_input).await;
+                            }
+
                             let _ = sender.send(WorkerInput::CheckLatestVersion).await;
                         },
                         |()| Message::None,
                     );
+
+                    return Task::batch([startup, self.fetch_toolchains()]);
                 }
                 WorkerEvent::SuccessCrate(details) => {
-                    self.fetch_progress = Some(self.fetch_progress.unwrap_or(0) + 1);
-
-                    let mut progress_status = 0.0;
-                    let total_item = self.crate_list.len();
-
-                    if let Some(progress) = self.fetch_progress {
-                        progress_status = (progress as f32 / total_item as f32) * 100.0;
-                    }
-
-                    self.lerp_state
-                        .lerp(FETCH_PROGRESS_KEY, f64::from(progress_status));
-
-                    self.lerp_state
-                        .lerp(FETCH_PROGRESS_HEIGHT_KEY, FETCH_PROGRESS_HEIGHT);
-
                     let description = details
                         .crate_data
                         .description
                         .clone()
                         .unwrap_or(String::from("The crate has no description"));
 
-                    let latest_version = Version::parse(&details.crate_data.max_version);
-
-                    if let Err(e) = latest_version {
-                        error!(
-                            "Failed to parse version: {e}. Was parsing {}",
-                            details.crate_data.max_version
-                        );
+                    let Some(target_crate) = self.crate_list.get_mut(&details.crate_data.name)
+                    else {
                         return Task::none();
-                    }
-
-                    let latest_version = latest_version.unwrap();
-
-                    let target_crate = self.crate_list.get_mut(&details.crate_data.name).unwrap();
-
-                    self.delete_crates.remove(&details.crate_data.name);
+                    };
 
                     if let Some(config) = &mut self.config {
-                        let version_data = &details.versions[0];
-                        let feature_list = version_data.features.keys().cloned().collect();
-
-                        config.update_cache(
+                        config.update_description(
                             details.crate_data.name.clone(),
                             description.clone(),
-                            feature_list,
-                            latest_version.to_string(),
                         );
                     }
 
                     target_crate.description = description;
-                    target_crate.crates_version = Some(latest_version);
                     target_crate.crate_response = Some(*details);
                 }
-                WorkerEvent::ErrorCrate(index) => {
-                    self.fetch_progress = Some(index + 1);
+                WorkerEvent::SuccessIndex {
+                    crate_name,
+                    versions,
+                } => {
+                    self.advance_fetch_progress();
 
-                    let mut progress_status = 0.0;
-                    let total_item = self.crate_list.len();
+                    let Some(target_crate) = self.crate_list.get_mut(&crate_name) else {
+                        return Task::none();
+                    };
 
-                    if let Some(progress) = self.fetch_progress {
-                        progress_status = (progress as f32 / total_item as f32) * 100.0;
+                    if latest_release(&versions, true).is_none() {
+                        error!("No available version found for {crate_name}");
+                        return Task::none();
                     }
 
-                    self.lerp_state
-                        .lerp(FETCH_PROGRESS_KEY, f64::from(progress_status));
+                    target_crate.set_versions(versions);
 
-                    self.lerp_state
-                        .lerp(FETCH_PROGRESS_HEIGHT_KEY, FETCH_PROGRESS_HEIGHT);
+                    self.delete_crates.remove(&crate_name);
+
+                    if let Some(config) = &mut self.config
+                        && let Some(target) = &target_crate.crates_version
+                    {
+                        config.update_cache(
+                            crate_name,
+                            target_crate.available_features().clone(),
+                            target.to_string(),
+                        );
+                    }
+
+                    if target_crate.is_yanked() {
+                        let log = format!(
+                            "{} v{} has been yanked from the registry",
+                            target_crate.name, target_crate.version
+                        );
+
+                        info!("{log}");
+                        self.push_log(log);
+                    }
+                }
+                WorkerEvent::ErrorCrate(crate_name) => {
+                    error!("Failed to fetch details of {crate_name}");
+
+                    self.advance_fetch_progress();
                 }
                 WorkerEvent::DoneCrateCheck => {
                     self.fetch_progress = None;
                     self.lerp_state.lerp("fetch_progress_height", 0.0);
                 }
-                WorkerEvent::Log(log) => {
-                    self.logs.push(log);
-
-                    if self.logs.len() > 1000 {
-                        self.logs.remove(0);
+                WorkerEvent::CrateLog(crate_name, log) => {
+                    self.operation_logs
+                        .entry(crate_name)
+                        .or_default()
+                        .push(log.clone());
+
+                    // Lines of builds running side by side would be impossible to tell apart
+                    if self.install_defaults().parallel > 1 {
+                        self.push_log(format!("{crate_name}: {log}"));
+                    } else {
+                        self.push_log(log);
                     }
                 }
-                WorkerEvent::DoneUpdate => {
-                    if let Some(name) = &self.operation_crate {
-                        let target_crate = self.crate_list.get_mut(&name.name).unwrap();
-                        target_crate.version = target_crate.crates_version.clone().unwrap();
+                WorkerEvent::CrateResult {
+                    crate_name,
+                    operation,
+                    result,
+                } => {
+                    let log = self.operation_logs.remove(&crate_name).unwrap_or_default();
 
-                        if let Some(hash) = &target_crate.latest_hash {
-                            target_crate.local_hash = Some(hash.clone());
+                    self.operation_crates
+                        .retain(|working| working.name != crate_name);
+                    self.lerp_state.remove(&build_progress_key(&crate_name));
+                    self.operation_done += 1;
+                    self.update_lerp_states_operation_container();
+                    self.update_lerp_states_operation_progress();
+
+                    if result.is_success() {
+                        match operation {
+                            OperationType::Update => {
+                                // A rebuild installs the same version and commit again, and an
+                                // edited command may install anything. Both are read back from
+                                // crates2.json once the batch is done.
+                                let keep_installed =
+                                    self.update_crates.get(&crate_name).is_some_and(|staged| {
+                                        staged.rebuild || staged.command.is_some()
+                                    });
+
+                                if let Some(target_crate) = self.crate_list.get_mut(&crate_name) {
+                                    if !keep_installed
+                                        && let Some(version) = &target_crate.crates_version
+                                    {
+                                        target_crate.version = version.clone();
+                                    }
+
+                                    target_crate.choose_version(None);
+
+                                    if !keep_installed && let Some(hash) = &target_crate.latest_hash
+                                    {
+                                        target_crate.local_hash = Some(hash.clone());
+                                    }
+
+                                    target_crate.failure = None;
+                                }
+                            }
+                            OperationType::Delete => {
+                                self.crate_list.remove(&crate_name);
+                            }
+                            OperationType::Rollback => {}
+                        }
+                    } else {
+                        error!("Operation on {crate_name} failed: {}", result.summary());
+
+                        if let Some(target_crate) = self.crate_list.get_mut(&crate_name) {
+                            target_crate.failure = Some(CrateFailure { result, log });
                         }
                     }
+                }
+                WorkerEvent::Cancelled(mut report) => {
+                    // Deletions only start once the updates are done
+                    if self.operation == Some(OperationType::Update) {
+                        report
+                            .not_started
+                            .extend(self.delete_crates.keys().cloned());
+                    }
 
+                    let lines = [
+                        ("Finished", &report.finished),
+                        ("Aborted", &report.aborted),
+                        ("Never started", &report.not_started),
+                    ]
+                    .into_iter()
+                    .filter(|(_, crates)| !crates.is_empty())
+                    .map(|(label, crates)| format!("{label}: {}", crates.join(", ")))
+                    .collect::<Vec<String>>();
+
+                    self.push_log(String::from("Operation cancelled"));
+
+                    for line in lines {
+                        info!("{line}");
+                        self.push_log(line);
+                    }
+
+                    self.operation_cancelled = true;
+                }
+                WorkerEvent::DoneUpdate => {
                     let Some(mut worker) = self.worker.clone() else {
                         return Task::none();
                     };
 
-                    if let Some(name) = &self.operation_crate {
-                        let target_crate = self.crate_list.get_mut(&name.name).unwrap();
-                        target_crate.version = target_crate.crates_version.clone().unwrap();
-                    }
-
-                    self.operation_crate = None;
+                    self.operation_crates.clear();
 
-                    return if self.delete_crates.is_empty() {
-                        self.operation_crate = None;
+                    // A stop pressed as the updates finished still keeps the removals from
+                    // starting
+                    return if self.delete_crates.is_empty()
+                        || self.operation_cancelled
+                        || self.stopping_operation
+                    {
+                        self.operation_cancelled = false;
+                        self.stopping_operation = false;
+                        self.operation = None;
+                        self.operation_done = 0;
                         self.delete_crates.clear();
                         self.update_crates.clear();
+                        self.clear_chosen_versions();
                         self.update_lerp_states_operation_container();
-                        Task::none()
+                        Task::batch([self.refresh_build_cache(), self.reconcile_crates()])
                     } else {
-                        let crate_list = self.delete_crates.keys().cloned().collect();
+                        let crate_list = self.staged_deletes();
 
                         Task::perform(
                             async move {
@@ -229,45 +535,65 @@ impl MainWindow {
                         )
                     };
                 }
-                WorkerEvent::DoneDelete => {
-                    if let Some(name) = &self.operation_crate {
-                        self.crate_list.remove(&name.name);
+                WorkerEvent::HistoryRecorded(entry) => {
+                    self.history.push(*entry);
+
+                    if self.history.len() > HISTORY_LIMIT {
+                        self.history.remove(0);
+                        self.history_selected =
+                            self.history_selected.and_then(|index| index.checked_sub(1));
                     }
+                }
+                WorkerEvent::DoneRollback { crate_name, result } => {
+                    let log = match result {
+                        Ok(()) => format!("Rolled back {crate_name}"),
+                        Err(e) => {
+                            error!("Failed to roll back {crate_name}: {e}");
+                            format!("Failed to roll back {crate_name}: {e}")
+                        }
+                    };
+
+                    self.push_log(log);
+                    self.operation_logs.remove(&crate_name);
+                    self.operation = None;
+                    self.operation_crates.clear();
+                    self.update_lerp_states_operation_container();
 
+                    return self.reconcile_crates();
+                }
+                WorkerEvent::DoneDelete => {
+                    self.operation_cancelled = false;
+                    self.stopping_operation = false;
                     self.delete_crates.clear();
                     self.update_crates.clear();
+                    self.clear_chosen_versions();
                     self.update_lerp_states_operation_container();
-                    self.operation_crate = None;
-                }
-                WorkerEvent::Updating((name, index)) => {
-                    if let Some(name) = &self.operation_crate {
-                        let target_crate = self.crate_list.get_mut(&name.name).unwrap();
-                        target_crate.version = target_crate.crates_version.clone().unwrap();
+                    self.operation = None;
+                    self.operation_crates.clear();
+                    self.operation_done = 0;
 
-                        if let Some(hash) = &target_crate.latest_hash {
-                            target_crate.local_hash = Some(hash.clone());
-                        }
-                    }
+                    return self.reconcile_crates();
+                }
+                WorkerEvent::Updating(name) => {
+                    self.lerp_state.lerp(&build_progress_key(&name), 0.0);
 
-                    let operation_crate = OperationCrate {
+                    self.operation = Some(OperationType::Update);
+                    self.operation_crates.push(OperationCrate {
                         name,
-                        index,
-                        operation_type: OperationType::Update,
-                    };
-                    self.operation_crate = Some(operation_crate);
+                        started: Instant::now(),
+                        build: None,
+                    });
+                    self.update_lerp_states_operation_container();
                     self.update_lerp_states_operation_progress();
                 }
-                WorkerEvent::Deleting((name, index)) => {
-                    if let Some(name) = &self.operation_crate {
-                        self.crate_list.remove(&name.name);
-                    }
-
-                    let operation_crate = OperationCrate {
+                WorkerEvent::Deleting(name) => {
+                    self.operation = Some(OperationType::Delete);
+                    self.operation_crates.push(OperationCrate {
                         name,
-                        index,
-                        operation_type: OperationType::Delete,
-                    };
-                    self.operation_crate = Some(operation_crate);
+                        started: Instant::now(),
+                        build: None,
+                    });
+                    self.update_lerp_states_operation_container();
                     self.update_lerp_states_operation_progress();
                 }
                 WorkerEvent::ReadyFailed => {
@@ -281,30 +607,163 @@ impl MainWindow {
                     info!("Got commit hash {commit} for {crate_name}");
 
                     if load_bar {
-                        self.fetch_progress = Some(self.fetch_progress.unwrap_or(0) + 1);
+                        self.advance_fetch_progress();
+                    }
 
-                        let mut progress_status = 0.0;
-                        let total_item = self.crate_list.len();
+                    let Some(target_crate) = self.crate_list.get_mut(&crate_name) else {
+                        return Task::none();
+                    };
 
-                        if let Some(progress) = self.fetch_progress {
-                            progress_status = (progress as f32 / total_item as f32) * 100.0;
+                    target_crate.set_latest_commit(commit);
+                }
+                WorkerEvent::ErrorGitCommit {
+                    crate_name,
+                    load_bar,
+                } => {
+                    error!("Failed to get the latest commit of {crate_name}");
+
+                    if load_bar {
+                        self.advance_fetch_progress();
+                    }
+                }
+                WorkerEvent::SearchResults { query, result } => {
+                    // A newer search is on its way
+                    if query.trim() != self.discover.query.trim() {
+                        return Task::none();
+                    }
+
+                    self.discover.searching = false;
+                    self.discover.selected = None;
+
+                    match result {
+                        Ok(results) => {
+                            self.discover.status = results
+                                .is_empty()
+                                .then(|| format!("No crates found for {query}"));
+                            self.discover.results = results;
                         }
+                        Err(e) => {
+                            error!("{e}");
+                            self.discover.status = Some(e);
+                        }
+                    }
+                }
+                WorkerEvent::CrateDetails { crate_name, result } => {
+                    if let Err(e) = &result {
+                        error!("{e}");
+                    }
 
-                        self.lerp_state
-                            .lerp(FETCH_PROGRESS_KEY, f64::from(progress_status));
+                    self.discover.details.insert(crate_name, result);
+                }
+                WorkerEvent::ReleaseBins {
+                    crate_name,
+                    version,
+                    bins,
+                } => {
+                    let release_bins = Some((version, bins));
 
-                        self.lerp_state
-                            .lerp(FETCH_PROGRESS_HEIGHT_KEY, FETCH_PROGRESS_HEIGHT);
+                    if let Some(staged) = self.update_crates.get_mut(&crate_name) {
+                        staged.release_bins.clone_from(&release_bins);
                     }
 
-                    let target_crate = self.crate_list.get_mut(&crate_name).unwrap();
+                    if let Some(target_crate) = self.crate_list.get_mut(&crate_name) {
+                        target_crate.release_bins = release_bins;
+                    }
 
-                    target_crate.latest_hash = Some(commit);
+                    if self.review.show_modal {
+                        self.refresh_review();
+                    }
                 }
                 WorkerEvent::NewUpdateAvailable(details) => {
                     self.update_available = Some(details);
                     self.update_lerp_states_update_modal();
                 }
+                WorkerEvent::BuildProgress {
+                    crate_name,
+                    progress,
+                } => {
+                    let Some(working) = self
+                        .operation_crates
+                        .iter_mut()
+                        .find(|working| working.name == crate_name)
+                    else {
+                        return Task::none();
+                    };
+
+                    if let Some(fraction) = progress.fraction() {
+                        self.lerp_state
+                            .lerp(&build_progress_key(&crate_name), fraction * 100.0);
+                    }
+
+                    working.build = Some(progress);
+                }
+                WorkerEvent::BuildCache { size, sccache } => {
+                    self.build_cache_size = Some(size);
+                    self.sccache = sccache;
+                }
+                WorkerEvent::BuildCacheCleaned(result) => {
+                    let log = match result {
+                        Ok(freed) => {
+                            self.build_cache_size = Some(0);
+                            format!("Cleaned the build cache, freed {}", format_size(freed))
+                        }
+                        Err(e) => {
+                            error!("{e}");
+                            format!("Failed to clean the build cache: {e}")
+                        }
+                    };
+
+                    info!("{log}");
+                    self.push_log(log);
+                }
+                WorkerEvent::RustcVersion { toolchain, result } => match (toolchain, result) {
+                    (None, Ok(rustc)) => {
+                        info!("Using {}", rustc.summary());
+                        self.rustc = Some(rustc);
+                        self.apply_msrv_limit();
+                    }
+                    (Some(toolchain), Ok(rustc)) => {
+                        info!("Toolchain {toolchain} uses {}", rustc.summary());
+                        self.toolchains.insert(toolchain, rustc);
+                        self.apply_msrv_limit();
+                    }
+                    (toolchain, Err(e)) => {
+                        let log = match toolchain {
+                            Some(toolchain) => {
+                                format!("Failed to detect rustc of {toolchain}: {e}")
+                            }
+                            None => format!("Failed to detect rustc: {e}"),
+                        };
+
+                        error!("{log}");
+                        self.push_log(log);
+                    }
+                },
+                WorkerEvent::AdvisoryScan(result) => {
+                    let mut found = match result {
+                        Ok(found) => found,
+                        Err(e) => {
+                            info!("Skipping advisory scan: {e}");
+                            return Task::none();
+                        }
+                    };
+
+                    let mut lines = Vec::new();
+
+                    for target_crate in self.crate_list.values_mut() {
+                        target_crate.advisories =
+                            found.remove(&target_crate.name).unwrap_or_default();
+
+                        for advisory in &target_crate.advisories {
+                            lines.push(format!("{}: {}", target_crate.name, advisory.describe()));
+                        }
+                    }
+
+                    for line in lines {
+                        info!("{line}");
+                        self.push_log(line);
+                    }
+                }
             },
             Message::Hovering(index) => {
                 self.hovering = Some(index);
@@ -325,72 +784,124 @@ impl MainWindow {
             Message::CancelOperation => {
                 self.delete_crates.clear();
                 self.update_crates.clear();
+                self.manifest.pending.clear();
+                self.clear_chosen_versions();
                 self.update_lerp_states_operation_container();
             }
-            Message::ApplyOperation => {
+            Message::StopOperation => {
                 let Some(mut worker) = self.worker.clone() else {
                     return Task::none();
                 };
 
-                let to_return;
-
-                if !self.update_crates.is_empty() {
-                    let crate_list = self.update_crates.values().cloned().collect();
-
-                    to_return = Task::perform(
-                        async move {
-                            let _ = worker.send(WorkerInput::UpdateCrates(crate_list)).await;
-                        },
-                        |()| Message::None,
-                    );
-                } else if !self.delete_crates.is_empty() {
-                    let crate_list = self.delete_crates.keys().cloned().collect();
-
-                    to_return = Task::perform(
-                        async move {
-                            let _ = worker.send(WorkerInput::DeleteCrates(crate_list)).await;
-                        },
-                        |()| Message::None,
-                    );
-                } else {
-                    to_return = Task::none();
-                }
+                self.stopping_operation = true;
 
-                return to_return;
+                return Task::perform(
+                    async move {
+                        let _ = worker.send(WorkerInput::CancelOperation).await;
+                    },
+                    |()| Message::None,
+                );
             }
             Message::ShowLog => {
+                self.log_filter = None;
+                self.showing = Page::Logs;
+            }
+            Message::ShowCrateLog(crate_name) => {
+                self.log_filter = Some(crate_name);
                 self.showing = Page::Logs;
             }
             Message::ShowCrates => {
                 self.showing = Page::Crates;
             }
+            Message::ShowHistory => {
+                self.showing = Page::History;
+            }
+            Message::ShowDiscover => {
+                self.showing = Page::Discover;
+            }
+            Message::Discover(event) => return self.update_discover(event),
+            Message::HistoryFilter(text) => {
+                self.history_filter = text;
+            }
+            Message::SelectHistory(index) => {
+                self.history_selected = Some(index);
+            }
             Message::UpdateAll => {
                 for item in self.crate_list.values() {
-                    if item.pinned {
-                        continue;
-                    }
-
-                    if let Some(crate_version) = item.crates_version.as_ref()
-                        && crate_version > &item.version
-                    {
+                    if item.should_update() {
                         self.update_crates.insert(item.name.clone(), item.clone());
-                        continue;
                     }
+                }
+                self.update_lerp_states_operation_container();
+            }
+            Message::FixVulnerable => {
+                for item in self.crate_list.values() {
+                    if !item.pinned && !item.advisories.is_empty() {
+                        let mut staged = item.clone();
 
-                    if let Some(git_hash) = item.latest_hash.as_ref()
-                        && let Some(local_hash) = item.local_hash.as_ref()
-                        && git_hash != local_hash
-                    {
-                        self.update_crates.insert(item.name.clone(), item.clone());
-                        continue;
-                    }
+                        // A vulnerable dependency only goes away when the crate is rebuilt
+                        staged.force = !item.has_update();
 
-                    if item.latest_hash.is_some() && item.local_hash.is_none() {
-                        self.update_crates.insert(item.name.clone(), item.clone());
+                        self.delete_crates.remove(&item.name);
+                        self.update_crates.insert(item.name.clone(), staged);
                     }
                 }
                 self.update_lerp_states_operation_container();
             }
+            Message::ToggleSharedTarget => {
+                if let Some(config) = &mut self.config {
+                    config.update_shared_target(!config.shared_target);
+                }
+
+                return self.refresh_build_cache();
+            }
+            Message::CleanBuildCache => {
+                if self.operation.is_some() {
+                    return Task::none();
+                }
+
+                let Some(mut worker) = self.worker.clone() else {
+                    return Task::none();
+                };
+
+                return Task::perform(
+                    async move {
+                        let _ = worker.send(WorkerInput::CleanBuildCache).await;
+                    },
+                    |()| Message::None,
+                );
+            }
+            Message::RebuildOutdated => {
+                let outdated = self
+                    .crate_list
+                    .values()
+                    .filter(|item| {
+                        // Leave crates that are already staged, an update rebuilds them anyway
+                        !item.pinned
+                            && !self.update_crates.contains_key(&item.name)
+                            && self
+                                .rustc_for(item)
+                                .is_some_and(|rustc| item.outdated_toolchain(rustc).is_some())
+                    })
+                    .cloned()
+                    .collect::<Vec<LocalCrate>>();
+
+                for mut staged in outdated {
+                    staged.force = true;
+                    staged.rebuild = true;
+
+                    self.delete_crates.remove(&staged.name);
+                    self.update_crates.insert(staged.name.clone(), staged);
+                }
+                self.update_lerp_states_operation_container();
+            }
+            Message::CheckCratesFile => {
+                // Cargo rewrites the file while an operation is running, the batch
+                // reconciles once it is done
+                if self.operation.is_none() && crates_file_modified() != self.crates_file_modified {
+                    return self.reconcile_crates();
+                }
+            }
             Message::None => {}
             Message::FeatureToggle {
                 crate_name,
@@ -422,20 +933,24 @@ impl MainWindow {
 
                     if !self.git_input.modal_text.is_empty() {
                         target_crate.git_link = Some(self.git_input.modal_text.clone());
+                        target_crate.git_ref = GitReference::new(
+                            self.git_input.reference_kind,
+                            &self.git_input.reference_text,
+                        );
+
+                        // The reference may point somewhere else now
+                        target_crate.latest_hash = None;
+                        target_crate.choose_version(None);
 
                         let Some(mut worker) = self.worker.clone() else {
                             return Task::none();
                         };
 
-                        // No fetching the hash for the same crate twice
-                        if target_crate.latest_hash.is_some() {
+                        let Some(git_source) = target_crate.git_source() else {
                             return Task::none();
-                        }
+                        };
 
-                        let to_send = HashMap::from([(
-                            target_crate.name.clone(),
-                            self.git_input.modal_text.clone(),
-                        )]);
+                        let to_send = HashMap::from([(target_crate.name.clone(), git_source)]);
 
                         return Task::perform(
                             async move {
@@ -449,7 +964,360 @@ impl MainWindow {
                 GitInputEvent::Input(text) => {
                     self.git_input.modal_text = text;
                 }
+                GitInputEvent::ReferenceKind(kind) => {
+                    self.git_input.reference_kind = kind;
+                }
+                GitInputEvent::ReferenceInput(text) => {
+                    self.git_input.reference_text = text;
+                }
+            },
+            Message::Policy(event) => match event {
+                PolicyEvent::Show(crate_name) => {
+                    let Some(target_crate) = self.crate_list.get(&crate_name) else {
+                        return Task::none();
+                    };
+
+                    self.policy_input.kind = target_crate.policy.kind();
+                    self.policy_input.text = target_crate.policy.value().to_string();
+                    self.policy_input.error = None;
+                    self.policy_input.crate_name = crate_name;
+                    self.policy_input.show_modal = true;
+                    self.lerp_state
+                        .lerp(POLICY_MODAL_WIDTH_KEY, POLICY_MODAL_WIDTH);
+                }
+                PolicyEvent::Hide => {
+                    self.policy_input.show_modal = false;
+                    self.lerp_state.lerp(POLICY_MODAL_WIDTH_KEY, 0.0);
+                }
+                PolicyEvent::Kind(kind) => {
+                    self.policy_input.kind = kind;
+                    self.policy_input.error = None;
+                }
+                PolicyEvent::Input(text) => {
+                    self.policy_input.text = text;
+                }
+                PolicyEvent::Submit => {
+                    let policy =
+                        match UpdatePolicy::new(self.policy_input.kind, &self.policy_input.text) {
+                            Ok(policy) => policy,
+                            Err(e) => {
+                                self.policy_input.error = Some(e);
+                                return Task::none();
+                            }
+                        };
+
+                    let crate_name = self.policy_input.crate_name.clone();
+
+                    let Some(target_crate) = self.crate_list.get_mut(&crate_name) else {
+                        return Task::none();
+                    };
+
+                    target_crate.policy = policy.clone();
+                    target_crate.choose_version(None);
+
+                    // The staged update may point at a release the policy no longer allows
+                    self.update_crates.remove(&crate_name);
+
+                    if let Some(config) = &mut self.config {
+                        config.update_policy(crate_name, policy);
+                    }
+
+                    self.policy_input.show_modal = false;
+                    self.lerp_state.lerp(POLICY_MODAL_WIDTH_KEY, 0.0);
+                    self.update_lerp_states_operation_container();
+                }
+            },
+            Message::Build(event) => match event {
+                BuildEvent::Show(crate_name) => {
+                    let Some(target_crate) = self.crate_list.get(&crate_name) else {
+                        return Task::none();
+                    };
+
+                    let build = &target_crate.build;
+
+                    self.build_input.toolchain = build.toolchain.clone().unwrap_or_default();
+                    self.build_input.env = join_words(&build.env_words());
+                    self.build_input.args = join_words(&build.extra_args);
+                    self.build_input.error = None;
+                    self.build_input.crate_name = crate_name;
+                    self.build_input.show_modal = true;
+                    self.lerp_state
+                        .lerp(BUILD_MODAL_WIDTH_KEY, BUILD_MODAL_WIDTH);
+                }
+                BuildEvent::Hide => {
+                    self.build_input.show_modal = false;
+                    self.lerp_state.lerp(BUILD_MODAL_WIDTH_KEY, 0.0);
+                }
+                BuildEvent::Toolchain(text) => {
+                    self.build_input.toolchain = text;
+                }
+                BuildEvent::Env(text) => {
+                    self.build_input.env = text;
+                }
+                BuildEvent::Args(text) => {
+                    self.build_input.args = text;
+                }
+                BuildEvent::Submit => {
+                    let toolchain = self.build_input.toolchain.trim().trim_start_matches('+');
+
+                    let build = match (
+                        parse_env(&self.build_input.env),
+                        split_words(&self.build_input.args),
+                    ) {
+                        (Ok(env), Ok(extra_args)) => BuildSettings {
+                            env,
+                            toolchain: (!toolchain.is_empty()).then(|| toolchain.to_string()),
+                            extra_args,
+                        },
+                        (Err(e), _) | (_, Err(e)) => {
+                            self.build_input.error = Some(e);
+                            return Task::none();
+                        }
+                    };
+
+                    let crate_name = self.build_input.crate_name.clone();
+
+                    let Some(target_crate) = self.crate_list.get_mut(&crate_name) else {
+                        return Task::none();
+                    };
+
+                    target_crate.build = build.clone();
+
+                    // A staged install runs with the settings it was staged with otherwise
+                    if let Some(staged) = self.update_crates.get_mut(&crate_name) {
+                        staged.build = build.clone();
+                    }
+
+                    if let Some(config) = &mut self.config {
+                        config.update_build(crate_name, build);
+                    }
+
+                    self.build_input.show_modal = false;
+                    self.lerp_state.lerp(BUILD_MODAL_WIDTH_KEY, 0.0);
+
+                    // The toolchain decides which releases the crate can build
+                    self.apply_msrv_limit();
+
+                    return self.fetch_toolchains();
+                }
+            },
+            Message::Review(event) => match event {
+                ReviewEvent::Show => {
+                    if self.operation.is_some() {
+                        return Task::none();
+                    }
+
+                    self.review.commands = self.review_commands();
+                    self.review.error = None;
+                    self.review.show_modal = true;
+                    self.lerp_state
+                        .lerp(REVIEW_MODAL_LENGTH_KEY, REVIEW_MODAL_LENGTH);
+
+                    return self.fetch_release_bins();
+                }
+                ReviewEvent::Hide => {
+                    self.review.show_modal = false;
+                    self.lerp_state.lerp(REVIEW_MODAL_LENGTH_KEY, 0.0);
+                }
+                ReviewEvent::Input(index, text) => {
+                    if let Some(command) = self.review.commands.get_mut(index) {
+                        command.text = text;
+                    }
+                }
+                ReviewEvent::Remove(index) => {
+                    if index < self.review.commands.len() {
+                        self.review.commands.remove(index);
+                    }
+                }
+                ReviewEvent::DryRun => {
+                    let lines = self
+                        .review
+                        .commands
+                        .iter()
+                        .map(|command| format!("Dry run: {}", command.masked()))
+                        .collect::<Vec<String>>();
+
+                    for line in lines {
+                        info!("{line}");
+                        self.push_log(line);
+                    }
+
+                    self.review.show_modal = false;
+                    self.lerp_state.lerp(REVIEW_MODAL_LENGTH_KEY, 0.0);
+                }
+                ReviewEvent::Confirm => {
+                    if self.operation.is_some() {
+                        return Task::none();
+                    }
+
+                    let parsed = self
+                        .review
+                        .commands
+                        .iter()
+                        .map(|command| {
+                            command
+                                .parse()
+                                .map(|words| (command.crate_name.clone(), command.operation, words))
+                                .map_err(|e| format!("{}: {e}", command.crate_name))
+                        })
+                        .collect::<Result<Vec<_>, String>>();
+
+                    let parsed = match parsed {
+                        Ok(parsed) => parsed,
+                        Err(e) => {
+                            self.review.error = Some(e);
+                            return Task::none();
+                        }
+                    };
+
+                    // Commands removed from the review are no longer staged
+                    self.update_crates.retain(|name, _| {
+                        parsed.iter().any(|(crate_name, operation, _)| {
+                            crate_name == name && *operation == OperationType::Update
+                        })
+                    });
+                    self.delete_crates.retain(|name, _| {
+                        parsed.iter().any(|(crate_name, operation, _)| {
+                            crate_name == name && *operation == OperationType::Delete
+                        })
+                    });
+
+                    for (crate_name, operation, command) in parsed {
+                        let staged = match operation {
+                            OperationType::Update => self.update_crates.get_mut(&crate_name),
+                            OperationType::Delete => self.delete_crates.get_mut(&crate_name),
+                            OperationType::Rollback => None,
+                        };
+
+                        if let Some(staged) = staged {
+                            staged.command = command;
+                        }
+                    }
+
+                    self.review.show_modal = false;
+                    self.lerp_state.lerp(REVIEW_MODAL_LENGTH_KEY, 0.0);
+                    self.update_lerp_states_operation_container();
+
+                    let task = self.apply_operation();
+                    self.commit_manifest();
+
+                    return task;
+                }
+            },
+            Message::VersionPicker(event) => match event {
+                VersionPickerEvent::Show(crate_name) => {
+                    self.version_picker.crate_name = crate_name;
+                    self.version_picker.show_modal = true;
+                    self.lerp_state
+                        .lerp(VERSION_MODAL_LENGTH_KEY, VERSION_MODAL_LENGTH);
+                }
+                VersionPickerEvent::Hide => {
+                    self.version_picker.show_modal = false;
+                    self.lerp_state.lerp(VERSION_MODAL_LENGTH_KEY, 0.0);
+                }
+                VersionPickerEvent::Select(version) => {
+                    let crate_name = self.version_picker.crate_name.clone();
+
+                    let Some(target_crate) = self.crate_list.get_mut(&crate_name) else {
+                        return Task::none();
+                    };
+
+                    target_crate.choose_version(Some(version));
+
+                    self.delete_crates.remove(&crate_name);
+                    self.update_crates.insert(crate_name, target_crate.clone());
+
+                    self.version_picker.show_modal = false;
+                    self.lerp_state.lerp(VERSION_MODAL_LENGTH_KEY, 0.0);
+                    self.update_lerp_states_operation_container();
+                }
+            },
+            Message::Manifest(event) => match event {
+                ManifestEvent::Show => {
+                    if self.manifest.path.is_empty() {
+                        self.manifest.path = default_manifest_path();
+                    }
+
+                    self.manifest.status.clear();
+                    self.manifest.show_modal = true;
+                    self.lerp_state
+                        .lerp(MANIFEST_MODAL_LENGTH_KEY, MANIFEST_MODAL_LENGTH);
+                }
+                ManifestEvent::Hide => {
+                    self.manifest.show_modal = false;
+                    self.lerp_state.lerp(MANIFEST_MODAL_LENGTH_KEY, 0.0);
+                }
+                ManifestEvent::Input(text) => {
+                    self.manifest.path = text;
+                }
+                ManifestEvent::Export => {
+                    let manifest = Manifest::from_crates(self.crate_list.values());
+
+                    let status = match manifest.save(&self.manifest.path) {
+                        Ok(()) => format!(
+                            "Exported {} crates to {}",
+                            manifest.crates.len(),
+                            self.manifest.path
+                        ),
+                        Err(e) => e,
+                    };
+
+                    info!("{status}");
+                    self.manifest.status = vec![status];
+                }
+                ManifestEvent::Import => {
+                    if self.operation.is_some() {
+                        self.manifest.status =
+                            vec![String::from("Wait for the running operation to finish")];
+                        return Task::none();
+                    }
+
+                    match Manifest::load(&self.manifest.path) {
+                        Ok(manifest) => self.stage_manifest(&manifest),
+                        Err(e) => {
+                            error!("{e}");
+                            self.manifest.status = vec![e];
+                        }
+                    }
+                }
             },
+            Message::Rollback(crate_name) => {
+                if self.operation.is_some() {
+                    return Task::none();
+                }
+
+                let Some(snapshot) = self
+                    .crate_list
+                    .get(&crate_name)
+                    .and_then(LocalCrate::rollback_target)
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+
+                let Some(mut worker) = self.worker.clone() else {
+                    return Task::none();
+                };
+
+                self.update_crates.remove(&crate_name);
+                self.delete_crates.remove(&crate_name);
+
+                // Nothing else may install or remove crates while the files are swapped
+                self.operation = Some(OperationType::Rollback);
+                self.operation_crates.push(OperationCrate {
+                    name: crate_name,
+                    started: Instant::now(),
+                    build: None,
+                });
+                self.update_lerp_states_operation_container();
+
+                return Task::perform(
+                    async move {
+                        let _ = worker.send(WorkerInput::Rollback(snapshot)).await;
+                    },
+                    |()| Message::None,
+                );
+            }
             Message::ToggleGitLink { crate_name } => {
                 let target_crate = self.crate_list.get_mut(&crate_name).unwrap();
 
@@ -457,6 +1325,8 @@ impl MainWindow {
                     self.git_input.show_modal = true;
                     self.git_input.modal_text = String::new();
                     self.git_input.crate_name.clone_from(&crate_name);
+                    self.git_input.reference_kind = target_crate.git_ref.kind();
+                    self.git_input.reference_text = target_crate.git_ref.name().to_string();
 
                     if let Some(crate_response) = &target_crate.crate_response {
                         if let Some(repo) = &crate_response.crate_data.repository {
@@ -486,15 +1356,58 @@ impl MainWindow {
                 }
             }
 
+            Message::ToggleKeepYanked(crate_name) => {
+                let target_crate = self.crate_list.get_mut(&crate_name).unwrap();
+
+                target_crate.keep_yanked = !target_crate.keep_yanked;
+
+                if target_crate.keep_yanked && target_crate.pinned {
+                    self.update_crates.remove(&crate_name);
+                }
+
+                if let Some(config) = &mut self.config {
+                    config.update_keep_yanked(crate_name, target_crate.keep_yanked);
+                }
+            }
+            Message::TogglePrereleases(crate_name) => {
+                let target_crate = self.crate_list.get_mut(&crate_name).unwrap();
+
+                target_crate.follow_prereleases = !target_crate.follow_prereleases;
+                target_crate.choose_version(None);
+
+                // The staged update may point at a release that is no longer followed
+                self.update_crates.remove(&crate_name);
+                self.update_lerp_states_operation_container();
+
+                if let Some(config) = &mut self.config {
+                    config.update_prereleases(crate_name, target_crate.follow_prereleases);
+                }
+            }
             Message::ToggleLocked(crate_name) => {
+                let defaults = self.install_defaults();
                 let target_crate = self.crate_list.get_mut(&crate_name).unwrap();
 
-                target_crate.locked = !target_crate.locked;
+                // Going back to the global default clears the override so the crate follows
+                // any later change of the default
+                let locked = !target_crate.uses_locked(&defaults);
+                target_crate.locked = (locked != defaults.locked).then_some(locked);
 
                 if let Some(config) = &mut self.config {
                     config.update_locked(crate_name, target_crate.locked);
                 }
             }
+            Message::ToggleAlwaysLocked => {
+                if let Some(config) = &mut self.config {
+                    config.update_always_locked(!config.always_locked);
+                }
+            }
+            Message::ToggleMsrvFallback => {
+                if let Some(config) = &mut self.config {
+                    config.update_msrv_fallback(!config.msrv_fallback);
+                }
+
+                self.apply_msrv_limit();
+            }
             Message::CloseUpdateModal => {
                 self.update_available = None;
                 self.update_lerp_states_update_modal();
@@ -509,4 +1422,134 @@ impl MainWindow {
 
         Task::none()
     }
+
+    fn update_discover(&mut self, event: DiscoverEvent) -> Task<Message> {
+        match event {
+            DiscoverEvent::Input(text) => {
+                self.discover.query = text;
+            }
+            DiscoverEvent::Search => {
+                let query = self.discover.query.trim().to_string();
+
+                if query.is_empty() {
+                    return Task::none();
+                }
+
+                let Some(mut worker) = self.worker.clone() else {
+                    return Task::none();
+                };
+
+                self.discover.searching = true;
+                self.discover.status = None;
+
+                let input = WorkerInput::search(query, self.config.as_ref());
+
+                return Task::perform(
+                    async move {
+                        let _ = worker.send(input).await;
+                    },
+                    |()| Message::None,
+                );
+            }
+            DiscoverEvent::Select(crate_name) => {
+                if self.discover.selected.as_ref() == Some(&crate_name) {
+                    self.discover.selected = None;
+                    return Task::none();
+                }
+
+                self.discover.selected = Some(crate_name.clone());
+                self.discover.activated_features.clear();
+                self.discover.no_default_features = false;
+                self.discover.git_link.clear();
+                self.discover.reference_kind = GitReferenceKind::default();
+                self.discover.reference_text.clear();
+
+                // Failed lookups are tried again
+                if let Some(Ok(_)) = self.discover.details.get(&crate_name) {
+                    return Task::none();
+                }
+
+                self.discover.details.remove(&crate_name);
+
+                let Some(mut worker) = self.worker.clone() else {
+                    return Task::none();
+                };
+
+                let input = WorkerInput::crate_details(crate_name, self.config.as_ref());
+
+                return Task::perform(
+                    async move {
+                        let _ = worker.send(input).await;
+                    },
+                    |()| Message::None,
+                );
+            }
+            DiscoverEvent::FeatureToggle(feature_name) => {
+                if feature_name == "default" {
+                    self.discover.no_default_features = !self.discover.no_default_features;
+                } else if !self.discover.activated_features.remove(&feature_name) {
+                    self.discover.activated_features.insert(feature_name);
+                }
+            }
+            DiscoverEvent::GitInput(text) => {
+                self.discover.git_link = text;
+            }
+            DiscoverEvent::ReferenceKind(kind) => {
+                self.discover.reference_kind = kind;
+            }
+            DiscoverEvent::ReferenceInput(text) => {
+                self.discover.reference_text = text;
+            }
+            DiscoverEvent::Install => {
+                let Some(crate_name) = self.discover.selected.clone() else {
+                    return Task::none();
+                };
+
+                if self.crate_list.contains_key(&crate_name) {
+                    return Task::none();
+                }
+
+                let mut item = LocalCrate::not_installed(&crate_name);
+                item.msrv_limit = self.msrv_limit(&item);
+
+                if let Some(result) = self
+                    .discover
+                    .results
+                    .iter()
+                    .find(|result| result.name == crate_name)
+                {
+                    item.description.clone_from(&result.description);
+                }
+
+                if let Some(Ok(details)) = self.discover.details.get(&crate_name) {
+                    item.set_versions(details.versions.clone());
+                }
+
+                item.activated_features
+                    .clone_from(&self.discover.activated_features);
+                item.no_default_features = self.discover.no_default_features;
+
+                let git_link = self.discover.git_link.trim();
+
+                if !git_link.is_empty() {
+                    item.git_link = Some(git_link.to_string());
+                    item.git_ref = GitReference::new(
+                        self.discover.reference_kind,
+                        &self.discover.reference_text,
+                    );
+                }
+
+                let log = format!("Queued install of {crate_name}");
+                info!("{log}");
+                self.push_log(log);
+
+                self.delete_crates.remove(&crate_name);
+                self.update_crates.insert(crate_name, item);
+                self.discover.selected = None;
+                self.update_lerp_states_operation_container();
+            }
+        }
+
+        Task::none()
+    }
 }
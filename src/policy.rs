@@ -0,0 +1,319 @@
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::index::IndexVersion;
+
+/// Which newer releases of a crate count as an update
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpdatePolicy {
+    #[default]
+    Any,
+    /// Only semver compatible releases, the way cargo reads a caret requirement. That is the
+    /// installed major version, or the minor version for 0.x and the patch version for 0.0.x.
+    Minor,
+    /// Stay on the installed major and minor version
+    Patch,
+    /// Only releases matching a version requirement
+    Hold(String),
+    /// Everything except one release
+    Ignore(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpdatePolicyKind {
+    #[default]
+    Any,
+    Minor,
+    Patch,
+    Hold,
+    Ignore,
+}
+
+impl UpdatePolicyKind {
+    pub const ALL: [UpdatePolicyKind; 5] = [
+        UpdatePolicyKind::Any,
+        UpdatePolicyKind::Minor,
+        UpdatePolicyKind::Patch,
+        UpdatePolicyKind::Hold,
+        UpdatePolicyKind::Ignore,
+    ];
+}
+
+impl fmt::Display for UpdatePolicyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            UpdatePolicyKind::Any => "Any",
+            UpdatePolicyKind::Minor => "Minor",
+            UpdatePolicyKind::Patch => "Patch",
+            UpdatePolicyKind::Hold => "Hold",
+            UpdatePolicyKind::Ignore => "Ignore",
+        };
+
+        write!(f, "{label}")
+    }
+}
+
+impl UpdatePolicy {
+    /// Build a policy from its kind and value, checking that the value parses
+    pub fn new(kind: UpdatePolicyKind, value: &str) -> Result<Self, String> {
+        let value = value.trim();
+
+        match kind {
+            UpdatePolicyKind::Any => Ok(UpdatePolicy::Any),
+            UpdatePolicyKind::Minor => Ok(UpdatePolicy::Minor),
+            UpdatePolicyKind::Patch => Ok(UpdatePolicy::Patch),
+            UpdatePolicyKind::Hold => VersionReq::parse(value)
+                .map(|_| UpdatePolicy::Hold(value.to_string()))
+                .map_err(|e| format!("Invalid version requirement {value}: {e}")),
+            UpdatePolicyKind::Ignore => Version::parse(value)
+                .map(|_| UpdatePolicy::Ignore(value.to_string()))
+                .map_err(|e| format!("Invalid version {value}: {e}")),
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> UpdatePolicyKind {
+        match self {
+            UpdatePolicy::Any => UpdatePolicyKind::Any,
+            UpdatePolicy::Minor => UpdatePolicyKind::Minor,
+            UpdatePolicy::Patch => UpdatePolicyKind::Patch,
+            UpdatePolicy::Hold(_) => UpdatePolicyKind::Hold,
+            UpdatePolicy::Ignore(_) => UpdatePolicyKind::Ignore,
+        }
+    }
+
+    #[must_use]
+    pub fn value(&self) -> &str {
+        match self {
+            UpdatePolicy::Any | UpdatePolicy::Minor | UpdatePolicy::Patch => "",
+            UpdatePolicy::Hold(value) | UpdatePolicy::Ignore(value) => value,
+        }
+    }
+
+    /// Whether moving from the installed version to a candidate is allowed
+    #[must_use]
+    pub fn allows(&self, installed: &Version, candidate: &Version) -> bool {
+        match self {
+            UpdatePolicy::Any => true,
+            UpdatePolicy::Minor => match (installed.major, installed.minor) {
+                (0, 0) => {
+                    candidate.major == 0
+                        && candidate.minor == 0
+                        && candidate.patch == installed.patch
+                }
+                (0, minor) => candidate.major == 0 && candidate.minor == minor,
+                (major, _) => candidate.major == major,
+            },
+            UpdatePolicy::Patch => {
+                candidate.major == installed.major && candidate.minor == installed.minor
+            }
+            UpdatePolicy::Hold(requirement) => {
+                VersionReq::parse(requirement).is_ok_and(|req| req.matches(candidate))
+            }
+            UpdatePolicy::Ignore(version) => &candidate.to_string() != version,
+        }
+    }
+
+    /// The newest release that is not yanked and the policy allows, skipping prereleases
+    /// unless they are followed and releases the given toolchain cannot build
+    #[must_use]
+    pub fn latest_allowed<'a>(
+        &self,
+        versions: &'a [IndexVersion],
+        installed: &Version,
+        prereleases: bool,
+        rustc: Option<&Version>,
+    ) -> Option<&'a IndexVersion> {
+        versions.iter().rev().find(|item| {
+            !item.yanked
+                && (prereleases || !item.is_prerelease())
+                && rustc.is_none_or(|rustc| item.supports(rustc))
+                && self.allows(installed, &item.version)
+        })
+    }
+}
+
+impl fmt::Display for UpdatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdatePolicy::Any => write!(f, "any"),
+            UpdatePolicy::Minor => write!(f, "minor"),
+            UpdatePolicy::Patch => write!(f, "patch"),
+            UpdatePolicy::Hold(requirement) => write!(f, "hold {requirement}"),
+            UpdatePolicy::Ignore(version) => write!(f, "ignore {version}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn release(version: &str, yanked: bool, rust_version: Option<&str>) -> IndexVersion {
+        IndexVersion {
+            version: v(version),
+            features: BTreeSet::new(),
+            yanked,
+            rust_version: rust_version.map(String::from),
+            published: None,
+        }
+    }
+
+    fn latest(
+        policy: &UpdatePolicy,
+        versions: &[IndexVersion],
+        installed: &str,
+        prereleases: bool,
+        rustc: Option<&str>,
+    ) -> Option<String> {
+        let rustc = rustc.map(v);
+
+        policy
+            .latest_allowed(versions, &v(installed), prereleases, rustc.as_ref())
+            .map(|item| item.version.to_string())
+    }
+
+    #[test]
+    fn minor_keeps_patch_for_0_0_x() {
+        let policy = UpdatePolicy::Minor;
+
+        assert!(policy.allows(&v("0.0.3"), &v("0.0.3")));
+        assert!(!policy.allows(&v("0.0.3"), &v("0.0.4")));
+        assert!(!policy.allows(&v("0.0.3"), &v("0.1.0")));
+        assert!(!policy.allows(&v("0.0.3"), &v("1.0.0")));
+    }
+
+    #[test]
+    fn minor_keeps_minor_for_0_x() {
+        let policy = UpdatePolicy::Minor;
+
+        assert!(policy.allows(&v("0.4.1"), &v("0.4.9")));
+        assert!(!policy.allows(&v("0.4.1"), &v("0.5.0")));
+        assert!(!policy.allows(&v("0.4.1"), &v("1.0.0")));
+    }
+
+    #[test]
+    fn minor_keeps_major_from_1() {
+        let policy = UpdatePolicy::Minor;
+
+        assert!(policy.allows(&v("1.2.3"), &v("1.9.0")));
+        assert!(policy.allows(&v("2.0.0"), &v("2.1.0")));
+        assert!(!policy.allows(&v("1.2.3"), &v("2.0.0")));
+    }
+
+    #[test]
+    fn patch_keeps_major_and_minor() {
+        let policy = UpdatePolicy::Patch;
+
+        assert!(policy.allows(&v("1.2.3"), &v("1.2.9")));
+        assert!(!policy.allows(&v("1.2.3"), &v("1.3.0")));
+        assert!(!policy.allows(&v("0.2.3"), &v("0.3.0")));
+    }
+
+    #[test]
+    fn hold_follows_requirement() {
+        let policy = UpdatePolicy::Hold(String::from(">=1, <3"));
+
+        assert!(policy.allows(&v("0.9.0"), &v("1.0.0")));
+        assert!(policy.allows(&v("1.0.0"), &v("2.5.0")));
+        assert!(!policy.allows(&v("1.0.0"), &v("3.0.0")));
+        assert!(!policy.allows(&v("1.0.0"), &v("0.9.0")));
+
+        // Prereleases only match a requirement naming the same release
+        assert!(!UpdatePolicy::Hold(String::from(">=1")).allows(&v("1.0.0"), &v("2.0.0-beta.1")));
+
+        // A requirement that no longer parses allows nothing
+        assert!(!UpdatePolicy::Hold(String::from("not a req")).allows(&v("1.0.0"), &v("1.1.0")));
+    }
+
+    #[test]
+    fn ignore_skips_one_release() {
+        let policy = UpdatePolicy::Ignore(String::from("1.3.0"));
+
+        assert!(!policy.allows(&v("1.2.0"), &v("1.3.0")));
+        assert!(policy.allows(&v("1.2.0"), &v("1.3.1")));
+        assert!(policy.allows(&v("1.2.0"), &v("2.0.0")));
+    }
+
+    #[test]
+    fn new_checks_values() {
+        assert_eq!(
+            UpdatePolicy::new(UpdatePolicyKind::Hold, " >=1 "),
+            Ok(UpdatePolicy::Hold(String::from(">=1")))
+        );
+        assert!(UpdatePolicy::new(UpdatePolicyKind::Hold, "latest").is_err());
+        assert!(UpdatePolicy::new(UpdatePolicyKind::Ignore, "1.2").is_err());
+    }
+
+    #[test]
+    fn latest_allowed_skips_prereleases_and_yanked() {
+        let versions = [
+            release("1.0.0", false, None),
+            release("1.1.0", false, None),
+            release("1.2.0", true, None),
+            release("1.3.0-rc.1", false, None),
+            release("2.0.0", false, None),
+        ];
+
+        assert_eq!(
+            latest(&UpdatePolicy::Any, &versions, "1.0.0", false, None).as_deref(),
+            Some("2.0.0")
+        );
+        assert_eq!(
+            latest(&UpdatePolicy::Minor, &versions, "1.0.0", false, None).as_deref(),
+            Some("1.1.0")
+        );
+        assert_eq!(
+            latest(&UpdatePolicy::Minor, &versions, "1.0.0", true, None).as_deref(),
+            Some("1.3.0-rc.1")
+        );
+        assert_eq!(
+            latest(
+                &UpdatePolicy::Ignore(String::from("2.0.0")),
+                &versions,
+                "1.0.0",
+                false,
+                None
+            )
+            .as_deref(),
+            Some("1.1.0")
+        );
+    }
+
+    #[test]
+    fn latest_allowed_respects_toolchain() {
+        let versions = [
+            release("1.0.0", false, Some("1.60")),
+            release("1.1.0", false, Some("1.80")),
+        ];
+
+        assert_eq!(
+            latest(
+                &UpdatePolicy::Any,
+                &versions,
+                "1.0.0",
+                false,
+                Some("1.70.0")
+            )
+            .as_deref(),
+            Some("1.0.0")
+        );
+        assert_eq!(
+            latest(
+                &UpdatePolicy::Any,
+                &versions,
+                "1.0.0",
+                false,
+                Some("1.80.0")
+            )
+            .as_deref(),
+            Some("1.1.0")
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+/// How far a running cargo build is, from its progress bar and `Compiling` lines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildProgress {
+    pub compiled: usize,
+    /// Unknown until cargo draws its progress bar
+    pub total: Option<usize>,
+    /// The unit, or units, being built right now
+    pub current: String,
+}
+
+/// What a line of cargo output meant for the build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildLine {
+    /// The redrawn progress bar, which is not worth logging
+    Bar,
+    /// A unit started compiling
+    Unit,
+    Other,
+}
+
+/// Split a redrawn progress bar such as `Building [=====>    ] 45/120: syn, serde` into its
+/// header and what follows the bar
+fn split_bar(line: &str) -> Option<(&str, &str)> {
+    let (header, rest) = line.trim_start().split_once(" [")?;
+    let (bar, rest) = rest.split_once(']')?;
+
+    (!header.contains(' ') && bar.chars().all(|c| matches!(c, '=' | '>' | ' ')))
+        .then_some((header, rest))
+}
+
+/// Read `45/120: syn, serde` into the counts and the current units
+fn parse_counts(rest: &str) -> Option<(usize, usize, &str)> {
+    let (counts, current) = rest
+        .split_once(':')
+        .map_or((rest, ""), |(counts, current)| (counts, current.trim()));
+
+    let (compiled, total) = counts.trim().split_once('/')?;
+
+    Some((compiled.parse().ok()?, total.parse().ok()?, current))
+}
+
+impl BuildProgress {
+    /// Follow a line of cargo output
+    pub fn update(&mut self, line: &str) -> BuildLine {
+        if let Some((header, rest)) = split_bar(line) {
+            // Downloads draw a bar as well, only the build counts
+            if header == "Building"
+                && let Some((compiled, total, current)) = parse_counts(rest)
+            {
+                self.compiled = compiled;
+                self.total = Some(total);
+
+                if !current.is_empty() {
+                    self.current = current.to_string();
+                }
+            }
+
+            return BuildLine::Bar;
+        }
+
+        if let Some(unit) = line.trim_start().strip_prefix("Compiling ") {
+            self.current = unit.trim().to_string();
+
+            // Without the bar, count units as they start
+            if self.total.is_none() {
+                self.compiled += 1;
+            }
+
+            return BuildLine::Unit;
+        }
+
+        BuildLine::Other
+    }
+
+    /// Compiled units out of the total, from 0 to 1
+    #[must_use]
+    pub fn fraction(&self) -> Option<f64> {
+        let total = self.total.filter(|total| *total > 0)?;
+
+        Some((self.compiled as f64 / total as f64).min(1.0))
+    }
+
+    /// The time left if the remaining units take as long as the ones so far
+    #[must_use]
+    pub fn remaining(&self, elapsed: Duration) -> Option<Duration> {
+        let fraction = self.fraction().filter(|fraction| *fraction > 0.0)?;
+
+        Some(elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cargo stderr for an install with the progress bar forced on, redrawn with carriage
+    /// returns between the `Compiling` lines
+    const STDERR: &str = "    Updating crates.io index\n  Downloaded 3 crates (412.5 KB) in 0.41s\n  Installing demo v1.2.0\n   Compiling proc-macro2 v1.0.86\n    Building [                         ] 0/57: proc-macro2(build.rs)\r   Compiling unicode-ident v1.0.12\n    Building [=>                       ] 3/57: unicode-ident, proc-macro2(build.rs), libc(build)\r    Building [=========>               ] 24/57: syn, serde_derive\r   Compiling syn v2.0.72\n    Building [=======================> ] 56/57: demo(bin)\r    Finished `release` profile [optimized] target(s) in 41.20s\n  Installing /home/me/.cargo/bin/demo\n";
+
+    /// Feed cargo output the way the worker reads it, split on carriage returns and lines
+    fn follow(stderr: &str) -> (BuildProgress, Vec<(String, BuildLine)>) {
+        let mut progress = BuildProgress::default();
+        let mut lines = Vec::new();
+
+        for chunk in stderr.split('\r') {
+            for line in chunk.lines() {
+                lines.push((line.to_string(), progress.update(line)));
+            }
+        }
+
+        (progress, lines)
+    }
+
+    #[test]
+    fn follows_bar_and_compiling_lines() {
+        let (progress, lines) = follow(STDERR);
+
+        assert_eq!(
+            progress,
+            BuildProgress {
+                compiled: 56,
+                total: Some(57),
+                current: String::from("demo(bin)"),
+            }
+        );
+
+        let kinds = lines
+            .iter()
+            .map(|(_, kind)| *kind)
+            .collect::<Vec<BuildLine>>();
+
+        assert_eq!(
+            kinds,
+            [
+                BuildLine::Other,
+                BuildLine::Other,
+                BuildLine::Other,
+                BuildLine::Unit,
+                BuildLine::Bar,
+                BuildLine::Unit,
+                BuildLine::Bar,
+                BuildLine::Bar,
+                BuildLine::Unit,
+                BuildLine::Bar,
+                BuildLine::Other,
+                BuildLine::Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_chunks_keep_the_last_bar() {
+        let mut progress = BuildProgress::default();
+
+        let chunks = STDERR.split('\r').collect::<Vec<&str>>();
+
+        // Stop at the third chunk, which ends mid-line with the bar before syn starts compiling
+        for chunk in &chunks[..3] {
+            for line in chunk.lines() {
+                progress.update(line);
+            }
+        }
+
+        assert_eq!(progress.compiled, 24);
+        assert_eq!(progress.total, Some(57));
+        assert_eq!(progress.current, "syn, serde_derive");
+        assert_eq!(progress.fraction(), Some(24.0 / 57.0));
+    }
+
+    #[test]
+    fn counts_compiling_lines_without_bar() {
+        let mut progress = BuildProgress::default();
+
+        for line in [
+            "   Compiling libc v0.2.155",
+            "   Compiling cfg-if v1.0.0",
+            "warning: unused variable: `x`",
+        ] {
+            progress.update(line);
+        }
+
+        assert_eq!(progress.compiled, 2);
+        assert_eq!(progress.total, None);
+        assert_eq!(progress.current, "cfg-if v1.0.0");
+        assert_eq!(progress.fraction(), None);
+        assert_eq!(progress.remaining(Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn ignores_other_bars() {
+        let mut progress = BuildProgress::default();
+
+        assert_eq!(
+            progress.update("    Fetch [=====>                 ] 25.00%, 1.20MiB/s"),
+            BuildLine::Bar
+        );
+        assert_eq!(
+            progress.update("error[E0425]: cannot find value `x` in this scope"),
+            BuildLine::Other
+        );
+        assert_eq!(
+            progress.update("  --> src/main.rs:2:5 [note] see above"),
+            BuildLine::Other
+        );
+        assert_eq!(progress, BuildProgress::default());
+    }
+
+    #[test]
+    fn estimates_remaining_time() {
+        let progress = BuildProgress {
+            compiled: 25,
+            total: Some(100),
+            current: String::new(),
+        };
+
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert_eq!(
+            progress.remaining(Duration::from_secs(10)),
+            Some(Duration::from_secs(30))
+        );
+
+        let finished = BuildProgress {
+            compiled: 120,
+            total: Some(100),
+            current: String::new(),
+        };
+
+        assert_eq!(finished.fraction(), Some(1.0));
+        assert_eq!(
+            finished.remaining(Duration::from_secs(10)),
+            Some(Duration::ZERO)
+        );
+    }
+}
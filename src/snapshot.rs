@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::ffi::OsString;
+use std::fs::{self, File, create_dir_all};
+use std::io::{self, Read as _, Write as _};
+use std::path::{Path, PathBuf};
+
+use crate::crates_file::{InstallInfo, cargo_home, crates_file_path, crates_toml_path};
+use crate::history::unix_now;
+use crate::utils::parse_git_link;
+
+/// A copy of the binaries of an installed crate, kept so an update can be undone without
+/// recompiling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub version: String,
+    /// The commit of a git install
+    pub hash: Option<String>,
+    /// The crates2.json key, `name version source`
+    pub key: String,
+    pub install_info: InstallInfo,
+    /// Seconds since the unix epoch
+    pub created: u64,
+    #[serde(skip)]
+    location: PathBuf,
+}
+
+impl Snapshot {
+    #[must_use]
+    pub fn label(&self) -> String {
+        match &self.hash {
+            Some(hash) => format!("v{} ({})", self.version, &hash[..hash.len().min(5)]),
+            None => format!("v{}", self.version),
+        }
+    }
+}
+
+fn snapshot_root(name: &str) -> Option<PathBuf> {
+    let mut location = dirs::data_local_dir()?;
+    location.push("crane");
+    location.push("snapshots");
+    location.push(name);
+
+    Some(location)
+}
+
+/// Every snapshot of a crate, newest first
+#[must_use]
+pub fn list_snapshots(name: &str) -> Vec<Snapshot> {
+    let Some(root) = snapshot_root(name) else {
+        return Vec::new();
+    };
+
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    let mut snapshots = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let location = entry.path();
+
+            let mut buf = String::new();
+            File::open(location.join("snapshot.json"))
+                .ok()?
+                .read_to_string(&mut buf)
+                .ok()?;
+
+            let mut snapshot: Snapshot = serde_json::from_str(&buf).ok()?;
+            snapshot.location = location;
+
+            Some(snapshot)
+        })
+        .collect::<Vec<Snapshot>>();
+
+    snapshots.sort_by_key(|snapshot| Reverse(snapshot.created));
+
+    snapshots
+}
+
+fn read_installs() -> Result<serde_json::Value, String> {
+    let path = crates_file_path().ok_or("Failed to get home directory")?;
+
+    let mut buf = String::new();
+    File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut buf))
+        .map_err(|e| format!("Failed to read crates2.json: {e}"))?;
+
+    serde_json::from_str(&buf).map_err(|e| format!("Failed to parse crates2.json: {e}"))
+}
+
+fn bin_dir() -> Result<PathBuf, String> {
+    let mut location = cargo_home().ok_or("Failed to get home directory")?;
+    location.push("bin");
+
+    Ok(location)
+}
+
+/// Copy the installed binaries of a crate before it is replaced, keeping at most `limit`
+/// snapshots of it. Nothing is saved for crates that are not installed yet.
+pub fn take_snapshot(name: &str, limit: usize) -> Result<Option<Snapshot>, String> {
+    if limit == 0 {
+        return Ok(None);
+    }
+
+    let installs = read_installs()?;
+    let prefix = format!("{name} ");
+
+    let Some((key, info)) = installs
+        .get("installs")
+        .and_then(serde_json::Value::as_object)
+        .and_then(|installs| installs.iter().find(|(key, _)| key.starts_with(&prefix)))
+    else {
+        return Ok(None);
+    };
+
+    let install_info: InstallInfo = serde_json::from_value(info.clone())
+        .map_err(|e| format!("Failed to parse the install entry of {name}: {e}"))?;
+
+    let mut parts = key.split(' ');
+    let version = parts.nth(1).unwrap_or_default().to_string();
+    let hash = parts
+        .next()
+        .and_then(parse_git_link)
+        .map(|(_, _, hash)| hash);
+
+    let mut location = snapshot_root(name).ok_or("Failed to get the data directory")?;
+
+    match &hash {
+        Some(hash) => location.push(format!("{version}-{hash}")),
+        None => location.push(&version),
+    }
+
+    // A snapshot of the same build is replaced
+    if location.exists() {
+        fs::remove_dir_all(&location).map_err(|e| format!("Failed to clear old snapshot: {e}"))?;
+    }
+
+    create_dir_all(&location).map_err(|e| format!("Failed to create snapshot directory: {e}"))?;
+
+    let bin_dir = bin_dir()?;
+
+    for bin in &install_info.bins {
+        fs::copy(bin_dir.join(bin), location.join(bin))
+            .map_err(|e| format!("Failed to copy {bin}: {e}"))?;
+    }
+
+    let snapshot = Snapshot {
+        name: name.to_string(),
+        version,
+        hash,
+        key: key.clone(),
+        install_info,
+        created: unix_now(),
+        location: location.clone(),
+    };
+
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| format!("Failed to serialize snapshot: {e}"))?;
+
+    File::create(location.join("snapshot.json"))
+        .and_then(|mut file| file.write_all(json.as_bytes()))
+        .map_err(|e| format!("Failed to write snapshot: {e}"))?;
+
+    for old in list_snapshots(name).into_iter().skip(limit) {
+        let _ = fs::remove_dir_all(&old.location);
+    }
+
+    Ok(Some(snapshot))
+}
+
+/// A file next to `path` to write first, so a rename replaces `path` in one step
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".crane-tmp");
+
+    path.with_file_name(name)
+}
+
+/// Swap the temporary file in, or drop it when it could not be written
+fn rename_over(temp: &Path, path: &Path, written: io::Result<()>) -> io::Result<()> {
+    let result = written.and_then(|()| fs::rename(temp, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(temp);
+    }
+
+    result
+}
+
+/// Replace a file with new content without ever leaving it half written
+fn replace_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let temp = temp_path(path);
+
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(content)?;
+        file.sync_all()
+    });
+
+    rename_over(&temp, path, written)
+}
+
+/// Replace a file with a copy of another, which also works while the old binary is running
+fn replace_with_copy(from: &Path, path: &Path) -> io::Result<()> {
+    let temp = temp_path(path);
+    let written = fs::copy(from, &temp).map(|_| ());
+
+    rename_over(&temp, path, written)
+}
+
+/// Put the binaries of a snapshot back and point crates2.json and .crates.toml at it
+pub fn restore_snapshot(snapshot: &Snapshot) -> Result<(), String> {
+    let mut installs = read_installs()?;
+    let prefix = format!("{} ", snapshot.name);
+
+    let bin_dir = bin_dir()?;
+
+    for bin in &snapshot.install_info.bins {
+        replace_with_copy(&snapshot.location.join(bin), &bin_dir.join(bin))
+            .map_err(|e| format!("Failed to restore {bin}: {e}"))?;
+    }
+
+    let Some(entries) = installs
+        .get_mut("installs")
+        .and_then(serde_json::Value::as_object_mut)
+    else {
+        return Err(String::from("crates2.json has no installs"));
+    };
+
+    let current_keys = entries
+        .keys()
+        .filter(|key| key.starts_with(&prefix))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    for key in &current_keys {
+        let Some(current) = entries.remove(key) else {
+            continue;
+        };
+
+        // Binaries only the newer version had would be left behind otherwise
+        let current_bins = current["bins"].as_array().cloned().unwrap_or_default();
+
+        for bin in current_bins.iter().filter_map(|bin| bin.as_str()) {
+            if !snapshot.install_info.bins.iter().any(|item| item == bin) {
+                let _ = fs::remove_file(bin_dir.join(bin));
+            }
+        }
+    }
+
+    let info = serde_json::to_value(&snapshot.install_info)
+        .map_err(|e| format!("Failed to serialize install entry: {e}"))?;
+
+    entries.insert(snapshot.key.clone(), info);
+
+    let json = serde_json::to_string(&installs)
+        .map_err(|e| format!("Failed to serialize crates2.json: {e}"))?;
+
+    let crates_file = crates_file_path().ok_or("Failed to get home directory")?;
+
+    replace_file(&crates_file, json.as_bytes())
+        .map_err(|e| format!("Failed to write crates2.json: {e}"))?;
+
+    restore_crates_toml(snapshot, &prefix)
+}
+
+fn restore_crates_toml(snapshot: &Snapshot, prefix: &str) -> Result<(), String> {
+    let path = crates_toml_path().ok_or("Failed to get home directory")?;
+
+    // Nothing to keep in sync when cargo never wrote the file
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let mut buf = String::new();
+    File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut buf))
+        .map_err(|e| format!("Failed to read .crates.toml: {e}"))?;
+
+    let mut crates_toml: toml::Table =
+        toml::from_str(&buf).map_err(|e| format!("Failed to parse .crates.toml: {e}"))?;
+
+    let v1 = crates_toml
+        .entry("v1")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+
+    let Some(v1) = v1.as_table_mut() else {
+        return Err(String::from(".crates.toml has an unexpected layout"));
+    };
+
+    v1.retain(|key, _| !key.starts_with(prefix));
+
+    let bins = snapshot
+        .install_info
+        .bins
+        .iter()
+        .cloned()
+        .map(toml::Value::String)
+        .collect();
+
+    v1.insert(snapshot.key.clone(), toml::Value::Array(bins));
+
+    let content = toml::to_string(&crates_toml)
+        .map_err(|e| format!("Failed to serialize .crates.toml: {e}"))?;
+
+    replace_file(&path, content.as_bytes())
+        .map_err(|e| format!("Failed to write .crates.toml: {e}"))
+}
@@ -0,0 +1,96 @@
+use semver::Version;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// A rustc cargo builds with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rustc {
+    pub version: Version,
+    /// The `rustc -vV` output, which cargo also records for every install
+    pub verbose: String,
+}
+
+impl Rustc {
+    /// The first line of the output, such as `rustc 1.85.0 (4d91de4e4 2025-02-17)`
+    #[must_use]
+    pub fn summary(&self) -> &str {
+        self.verbose.lines().next().unwrap_or_default()
+    }
+
+    /// Whether cargo recorded this rustc for an install, which also tells nightlies of the
+    /// same release apart by their commit
+    #[must_use]
+    pub fn built(&self, recorded: &str) -> bool {
+        recorded.trim() == self.verbose.trim()
+    }
+}
+
+/// The rustc cargo builds with, from `rustc -vV`. A toolchain picks another one through
+/// rustup the way `cargo +toolchain` does.
+pub async fn rustc_version(toolchain: Option<&str>) -> Result<Rustc, String> {
+    let mut command = Command::new("rustc");
+
+    if let Some(toolchain) = toolchain {
+        command.arg(format!("+{toolchain}"));
+    }
+
+    let output = command
+        .arg("-vV")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run rustc: {e}"))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let verbose = String::from_utf8_lossy(&output.stdout).to_string();
+
+    let Some(version) = parse_rustc(&verbose) else {
+        return Err(format!("Unexpected rustc output: {verbose}"));
+    };
+
+    Ok(Rustc { version, verbose })
+}
+
+/// The release from rustc version output such as `rustc 1.85.0 (4d91de4e4 2025-02-17)`,
+/// which is also what cargo records for every install
+#[must_use]
+fn parse_rustc(output: &str) -> Option<Version> {
+    let version = output.lines().next()?.split_whitespace().nth(1)?;
+
+    Version::parse(version).ok()
+}
+
+/// The host triple from `rustc -vV` output, such as `x86_64-unknown-linux-gnu`
+#[must_use]
+pub fn host_triple(verbose: &str) -> Option<&str> {
+    verbose
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::trim)
+}
+
+/// Whether a toolchain can build a release requiring the given `rust-version`. Nightly and
+/// beta toolchains count as the release they lead up to.
+#[must_use]
+pub fn supports(rustc: &Version, rust_version: &str) -> bool {
+    let mut parts = rust_version
+        .trim()
+        .split('.')
+        .map(|part| part.parse::<u64>().ok());
+
+    let (Some(Some(major)), minor, patch) = (parts.next(), parts.next(), parts.next()) else {
+        // Nothing sensible to compare against
+        return true;
+    };
+
+    let required = (
+        major,
+        minor.flatten().unwrap_or(0),
+        patch.flatten().unwrap_or(0),
+    );
+
+    (rustc.major, rustc.minor, rustc.patch) >= required
+}
@@ -1,7 +1,10 @@
 use iced::font::{Family, Weight};
 use iced::widget::button::Status;
-use iced::widget::{Button, button, center, container, mouse_area, opaque, stack};
+use iced::widget::{Button, button, center, container, mouse_area, opaque, stack, text_input};
 use iced::{Border, Color, Element, Font, Theme};
+use std::path::PathBuf;
+
+use crate::git::GitReference;
 
 pub fn bold() -> Font {
     Font {
@@ -198,20 +201,78 @@ where
     })
 }
 
-pub fn parse_git_link(link: &str) -> Option<(String, String)> {
+pub fn parse_git_link(link: &str) -> Option<(String, GitReference, String)> {
     if link.starts_with("(git+") {
         let stripped_text = link.strip_prefix("(git+")?.strip_suffix(")")?;
 
         stripped_text.split_once('#').map(|(git_link, hash)| {
             let split_git_link = git_link.split_once('?').unwrap_or((git_link, ""));
 
-            (split_git_link.0.to_string(), hash.to_string())
+            (
+                split_git_link.0.to_string(),
+                GitReference::from_query(split_git_link.1),
+                hash.to_string(),
+            )
         })
     } else {
         None
     }
 }
 
+/// Resolve a leading `~/` to the home directory
+#[must_use]
+pub fn expand_path(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(mut home) = dirs::home_dir()
+    {
+        home.push(rest);
+        return home;
+    }
+
+    PathBuf::from(path)
+}
+
+pub fn input_style(theme: &Theme, status: text_input::Status) -> text_input::Style {
+    let palette = theme.palette();
+
+    let active = text_input::Style {
+        background: (palette.background.base.color).into(),
+        border: Border {
+            radius: 8.0.into(),
+            width: 1.0,
+            color: palette.background.strong.color,
+        },
+        icon: palette.background.weak.text,
+        placeholder: palette.secondary.base.color,
+        value: palette.background.base.text,
+        selection: palette.primary.weak.color,
+    };
+
+    match status {
+        text_input::Status::Active => active,
+        text_input::Status::Hovered => text_input::Style {
+            border: Border {
+                color: palette.background.base.text,
+                ..active.border
+            },
+            ..active
+        },
+        text_input::Status::Focused { .. } => text_input::Style {
+            border: Border {
+                color: palette.primary.strong.color,
+                ..active.border
+            },
+            ..active
+        },
+        text_input::Status::Disabled => text_input::Style {
+            background: (palette.background.weak.color).into(),
+            value: active.placeholder,
+            placeholder: palette.background.strongest.color,
+            ..active
+        },
+    }
+}
+
 pub fn modal<'a, Message>(
     base: impl Into<Element<'a, Message>>,
     content: impl Into<Element<'a, Message>>,
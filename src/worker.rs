@@ -1,24 +1,43 @@
 use crates_io_api::{AsyncClient, CrateResponse};
-use iced::futures::StreamExt;
 use iced::futures::channel::mpsc::{self, Sender};
-use iced::futures::join;
+use iced::futures::{StreamExt, join, stream};
 use iced::task::{Never, Sipper, sipper};
 use log::{error, info};
 use reqwest::Client;
 use semver::Version;
 use serde::Deserialize;
 use sipper::Sender as SSender;
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
-use crate::LocalCrate;
+use crate::advisory::{Advisory, ScanTarget, scan_advisories};
+use crate::build_cache::{
+    clean_shared_target, dir_size, rustc_wrapper_configured, sccache_path, shared_target_dir,
+};
+use crate::build_settings::{join_words, mask_secrets, split_env};
+use crate::config::{Config, InstallDefaults};
+use crate::crates_file::installed_build;
+use crate::discover::{
+    CrateDetails, SearchResult, fetch_details, fetch_release_bins, search_crates, user_agent,
+};
+use crate::git::{GitReference, latest_commit};
+use crate::history::{HistoryAction, HistoryEntry, append_history, unix_now};
+use crate::index::{DEFAULT_INDEX_URL, IndexVersion, fetch_index};
+use crate::progress::{BuildLine, BuildProgress};
+use crate::snapshot::{Snapshot, restore_snapshot, take_snapshot};
+use crate::toolchain::{Rustc, rustc_version};
+use crate::{LocalCrate, OperationType};
 
-#[derive(Debug, Deserialize)]
-struct Commit {
-    sha: String,
-}
+/// How many index entries are fetched at the same time
+const INDEX_CONCURRENCY: usize = 16;
+
+/// How many git remotes are queried at the same time
+const GIT_CONCURRENCY: usize = 8;
 
 #[derive(Deserialize)]
 struct GithubRelease {
@@ -31,27 +50,88 @@ pub fn event_worker() -> impl Sipper<Never, WorkerEvent> {
         let (sender, mut receiver) = mpsc::channel(100);
         o.send(WorkerEvent::Ready(sender)).await;
 
+        let cancel_state = Arc::new(CancelState::default());
+
+        // Shared by searches and crate lookups so they reuse the same connections
+        let discover_client = Client::builder().user_agent(user_agent()).build().ok();
+
         loop {
             let Some(event) = receiver.next().await else {
                 continue;
             };
 
+            // Reset here rather than in the spawned task, where it could wipe a stop that was
+            // handled before the batch got going
+            if matches!(
+                event,
+                WorkerInput::UpdateCrates(..) | WorkerInput::DeleteCrates(_)
+            ) {
+                cancel_state.reset();
+            }
+
             let mut output = o.clone();
+            let cancel_state = cancel_state.clone();
+            let discover_client = discover_client.clone();
 
             tokio::spawn(async move {
                 match event {
-                    WorkerInput::GetCrateVersion(list, rate_limit) => {
-                        let app_version = env!("CARGO_PKG_VERSION");
+                    WorkerInput::GetCrateVersion {
+                        crates,
+                        index_url,
+                        rate_limit,
+                    } => {
+                        let user_agent = user_agent();
 
-                        let Ok(client) = AsyncClient::new(
-                            &format!("Crane/{app_version} (rusty.pickle94@gmail.com)"),
-                            Duration::from_millis(rate_limit),
-                        ) else {
+                        let Ok(client) = Client::builder().user_agent(&user_agent).build() else {
                             error!("Failed to create client");
                             output.send(WorkerEvent::ReadyFailed).await;
                             return;
                         };
-                        for (index, name) in list.into_iter().enumerate() {
+
+                        // Versions come from the sparse index, which is not rate limited
+                        let mut index_results = stream::iter(crates.clone())
+                            .map(|name| {
+                                let client = &client;
+                                let index_url = &index_url;
+
+                                async move {
+                                    info!("Fetching index entry: {name}");
+                                    let result = fetch_index(client, index_url, &name).await;
+                                    (name, result)
+                                }
+                            })
+                            .buffer_unordered(INDEX_CONCURRENCY);
+
+                        while let Some((name, result)) = index_results.next().await {
+                            match result {
+                                Ok(versions) => {
+                                    output
+                                        .send(WorkerEvent::SuccessIndex {
+                                            crate_name: name,
+                                            versions,
+                                        })
+                                        .await;
+                                }
+                                Err(e) => {
+                                    error!("Failed to fetch index entry: {e}");
+
+                                    output.send(WorkerEvent::ErrorCrate(name)).await;
+                                }
+                            }
+                        }
+
+                        output.send(WorkerEvent::DoneCrateCheck).await;
+
+                        // The API is only needed for descriptions and metadata so it can
+                        // trickle in at its own pace
+                        let Ok(client) =
+                            AsyncClient::new(&user_agent, Duration::from_millis(rate_limit))
+                        else {
+                            error!("Failed to create client");
+                            return;
+                        };
+
+                        for name in crates {
                             info!("Fetching crate: {name}");
                             let resp = client.get_crate(&name).await;
 
@@ -62,136 +142,306 @@ pub fn event_worker() -> impl Sipper<Never, WorkerEvent> {
                                         .await;
                                 }
                                 Err(e) => {
-                                    error!("Failed to fetch crate: {e}");
-
-                                    output.send(WorkerEvent::ErrorCrate(index)).await;
+                                    error!("Failed to fetch crate {name}: {e}");
                                 }
                             }
                         }
-                        output.send(WorkerEvent::DoneCrateCheck).await;
                     }
-                    WorkerInput::UpdateCrates(crate_list) => {
-                        for (index, item) in crate_list.into_iter().enumerate() {
-                            let mut full_command =
-                                vec![String::from("cargo"), String::from("install")];
-
-                            if let Some(git) = item.git_link {
-                                full_command.push(String::from("--git"));
-                                full_command.push(git);
-                                full_command.push(item.name.clone());
-                            } else {
-                                full_command.push(item.name.clone());
-                            }
+                    WorkerInput::UpdateCrates(crate_list, defaults) => {
+                        let mut report = CancelReport::default();
 
-                            if item.no_default_features {
-                                full_command.push(String::from("--no-default-features"));
-                            }
+                        // Up to the configured number of builds run at the same time. Cargo
+                        // locks a shared target directory for the whole build, so parallel
+                        // installs would only queue behind each other there.
+                        let parallel = if defaults.shared_target {
+                            1
+                        } else {
+                            defaults.parallel.max(1)
+                        };
 
-                            for feature in item.activated_features {
-                                full_command.push(String::from("--features"));
-                                full_command.push(feature);
-                            }
+                        let mut installs = stream::iter(crate_list)
+                            .map(|item| {
+                                let cancel_state = &cancel_state;
+                                let defaults = &defaults;
+                                let output = output.clone();
 
-                            output
-                                .send(WorkerEvent::Log(format!(
-                                    "Executing: {}",
-                                    full_command.join(" ")
-                                )))
-                                .await;
+                                async move {
+                                    if cancel_state.is_cancelled() {
+                                        return (item.name, None);
+                                    }
 
-                            full_command.remove(0);
+                                    let result =
+                                        install_crate(&item, defaults, cancel_state, output).await;
 
-                            output
-                                .send(WorkerEvent::Updating((item.name.clone(), index)))
-                                .await;
+                                    (item.name, Some(result))
+                                }
+                            })
+                            .buffer_unordered(parallel);
 
-                            let mut command = Command::new("cargo");
-                            command
-                                .args(full_command)
-                                .stdout(std::process::Stdio::piped())
-                                .stderr(std::process::Stdio::piped());
+                        while let Some((crate_name, result)) = installs.next().await {
+                            match result {
+                                Some(result) => report.add(&crate_name, &result),
+                                None => report.not_started.push(crate_name),
+                            }
+                        }
+
+                        drop(installs);
 
-                            run_command(&item.name, command, output.clone()).await;
+                        if cancel_state.is_cancelled() {
+                            output.send(WorkerEvent::Cancelled(report)).await;
                         }
 
                         output.send(WorkerEvent::DoneUpdate).await;
                     }
                     WorkerInput::DeleteCrates(crate_list) => {
-                        for (index, item) in crate_list.into_iter().enumerate() {
-                            let mut full_command = vec![
-                                String::from("cargo"),
-                                String::from("uninstall"),
-                                item.clone(),
-                            ];
+                        let mut report = CancelReport::default();
 
-                            output
-                                .send(WorkerEvent::Log(format!(
-                                    "Executing: {}",
-                                    full_command.join(" ")
-                                )))
-                                .await;
+                        for target in crate_list {
+                            let item = target.name;
+
+                            if cancel_state.is_cancelled() {
+                                report.not_started.push(item);
+                                continue;
+                            }
 
-                            full_command.remove(0);
+                            let full_command =
+                                target.command.unwrap_or_else(|| uninstall_command(&item));
+                            let mut log = Vec::new();
 
-                            output
-                                .send(WorkerEvent::Deleting((item.clone(), index)))
-                                .await;
+                            let started = unix_now();
+                            let timer = Instant::now();
+                            let from = installed_build(&item);
+
+                            output.send(WorkerEvent::Deleting(item.clone())).await;
 
-                            let mut command = Command::new("cargo");
+                            let line =
+                                format!("Executing: {}", join_words(&mask_secrets(&full_command)));
+                            crate_log(&mut output, &mut log, &item, line).await;
+
+                            let (variables, args) = split_env(&full_command);
+
+                            let mut command = Command::new(&args[0]);
                             command
-                                .args(full_command)
+                                .args(&args[1..])
+                                .envs(variables)
                                 .stdout(std::process::Stdio::piped())
                                 .stderr(std::process::Stdio::piped());
 
-                            run_command(&item, command, output.clone()).await;
+                            let (result, command_log) = run_command(
+                                &item,
+                                OperationType::Delete,
+                                command,
+                                &cancel_state,
+                                output.clone(),
+                            )
+                            .await;
+
+                            log.extend(command_log);
+
+                            report.add(&item, &result);
+
+                            record_history(
+                                &mut output,
+                                HistoryEntry {
+                                    crate_name: item.clone(),
+                                    action: HistoryAction::Uninstall,
+                                    from,
+                                    to: installed_build(&item),
+                                    features: Vec::new(),
+                                    command: mask_secrets(&full_command),
+                                    started,
+                                    duration_ms: elapsed_ms(timer),
+                                    result: result.summary(),
+                                    success: result.is_success(),
+                                    log,
+                                },
+                            )
+                            .await;
+
+                            output
+                                .send(WorkerEvent::CrateResult {
+                                    crate_name: item,
+                                    operation: OperationType::Delete,
+                                    result,
+                                })
+                                .await;
+                        }
+
+                        if cancel_state.is_cancelled() {
+                            output.send(WorkerEvent::Cancelled(report)).await;
                         }
 
                         output.send(WorkerEvent::DoneDelete).await;
                     }
-                    WorkerInput::GetGitCommit(repo_links, load_bar) => {
-                        let client = Client::new();
+                    WorkerInput::Rollback(snapshot) => {
+                        let crate_name = snapshot.name.clone();
+                        let mut log = Vec::new();
 
-                        for (crate_name, repo_link) in repo_links {
-                            info!("Fetching git commit: {crate_name} from {repo_link}");
+                        let started = unix_now();
+                        let timer = Instant::now();
+                        let from = installed_build(&crate_name);
 
-                            let parts: Vec<&str> =
-                                repo_link.trim_end_matches('/').split('/').collect();
-                            if parts.len() < 2 {
-                                continue;
-                            }
-                            let owner = parts[parts.len() - 2];
-                            let repo = parts[parts.len() - 1];
+                        let line = format!("Rolling back {crate_name} to {}", snapshot.label());
+                        crate_log(&mut output, &mut log, &crate_name, line).await;
 
-                            let api_url = format!(
-                                "https://api.github.com/repos/{owner}/{repo}/commits?per_page=1"
-                            );
+                        let result = restore_snapshot(&snapshot);
 
-                            let res = client
-                                .get(&api_url)
-                                .header("User-Agent", "reqwest") // GitHub requires a User-Agent
-                                .send()
-                                .await;
+                        if let Err(e) = &result {
+                            let line = format!("Failed to roll back: {e}");
+                            crate_log(&mut output, &mut log, &crate_name, line).await;
+                        }
 
-                            match res {
-                                Ok(response) => {
-                                    let commits: Vec<Commit> =
-                                        response.json().await.unwrap_or_default();
+                        let mut features = snapshot.install_info.features.clone();
+                        features.sort();
 
-                                    let Some(commit) = commits.first() else {
-                                        info!("No commits found for {crate_name}");
-                                        continue;
-                                    };
+                        record_history(
+                            &mut output,
+                            HistoryEntry {
+                                crate_name: crate_name.clone(),
+                                action: HistoryAction::Rollback,
+                                from,
+                                to: installed_build(&crate_name),
+                                features,
+                                command: Vec::new(),
+                                started,
+                                duration_ms: elapsed_ms(timer),
+                                result: if result.is_ok() {
+                                    String::from("Succeeded")
+                                } else {
+                                    String::from("Failed")
+                                },
+                                success: result.is_ok(),
+                                log,
+                            },
+                        )
+                        .await;
 
+                        output
+                            .send(WorkerEvent::DoneRollback { crate_name, result })
+                            .await;
+                    }
+                    WorkerInput::CancelOperation => {
+                        cancel_state.cancel();
+                    }
+                    WorkerInput::ScanAdvisories(db, targets) => {
+                        info!("Scanning {} crates against {}", targets.len(), db.display());
+
+                        let result = scan_advisories(&db, targets).await;
+                        output.send(WorkerEvent::AdvisoryScan(result)).await;
+                    }
+                    WorkerInput::GetGitCommit(repo_links, load_bar) => {
+                        let mut commit_results = stream::iter(repo_links)
+                            .map(|(crate_name, (repo_link, reference))| async move {
+                                info!(
+                                    "Fetching git commit: {crate_name} from {repo_link} ({reference})"
+                                );
+                                let result = latest_commit(&repo_link, &reference).await;
+                                (crate_name, result)
+                            })
+                            .buffer_unordered(GIT_CONCURRENCY);
+
+                        while let Some((crate_name, result)) = commit_results.next().await {
+                            match result {
+                                Ok(commit) => {
                                     output
                                         .send(WorkerEvent::SuccessGitCommit {
                                             crate_name,
-                                            commit: commit.sha.clone(),
+                                            commit,
                                             load_bar,
                                         })
                                         .await;
                                 }
                                 Err(e) => {
-                                    error!("Failed to fetch commit: {e}");
+                                    error!("Failed to fetch commit for {crate_name}: {e}");
+
+                                    output
+                                        .send(WorkerEvent::ErrorGitCommit {
+                                            crate_name,
+                                            load_bar,
+                                        })
+                                        .await;
+                                }
+                            }
+                        }
+                    }
+                    WorkerInput::GetRustcVersion(toolchain) => {
+                        let result = rustc_version(toolchain.as_deref()).await;
+                        output
+                            .send(WorkerEvent::RustcVersion { toolchain, result })
+                            .await;
+                    }
+                    WorkerInput::GetBuildCache => {
+                        // Walking a large target directory takes a while
+                        let size = tokio::task::spawn_blocking(|| {
+                            shared_target_dir().map_or(0, |dir| dir_size(&dir))
+                        })
+                        .await
+                        .unwrap_or_default();
+
+                        output
+                            .send(WorkerEvent::BuildCache {
+                                size,
+                                sccache: sccache_path().is_some(),
+                            })
+                            .await;
+                    }
+                    WorkerInput::CleanBuildCache => {
+                        let result = tokio::task::spawn_blocking(clean_shared_target)
+                            .await
+                            .unwrap_or_else(|e| {
+                                Err(format!("Failed to clean the build cache: {e}"))
+                            });
+                        output.send(WorkerEvent::BuildCacheCleaned(result)).await;
+                    }
+                    WorkerInput::SearchCrates { query, rate_limit } => {
+                        info!("Searching crates.io for {query}");
+
+                        let result = match &discover_client {
+                            Some(client) => search_crates(client, &query, rate_limit).await,
+                            None => Err(String::from("Failed to create client")),
+                        };
+                        output
+                            .send(WorkerEvent::SearchResults { query, result })
+                            .await;
+                    }
+                    WorkerInput::GetCrateDetails { name, index_url } => {
+                        info!("Fetching details of {name}");
+
+                        let result = match &discover_client {
+                            Some(client) => fetch_details(client, &index_url, &name).await,
+                            None => Err(String::from("Failed to create client")),
+                        };
+                        output
+                            .send(WorkerEvent::CrateDetails {
+                                crate_name: name,
+                                result,
+                            })
+                            .await;
+                    }
+                    WorkerInput::GetReleaseBins {
+                        releases,
+                        rate_limit,
+                    } => {
+                        let Some(client) = &discover_client else {
+                            error!("Failed to create client");
+                            return;
+                        };
+
+                        for (crate_name, version, result) in
+                            fetch_release_bins(client, releases, rate_limit).await
+                        {
+                            match result {
+                                Ok(bins) => {
+                                    output
+                                        .send(WorkerEvent::ReleaseBins {
+                                            crate_name,
+                                            version,
+                                            bins,
+                                        })
+                                        .await;
+                                }
+                                Err(e) => {
+                                    error!("Failed to fetch the binaries of {crate_name}: {e}")
                                 }
                             }
                         }
@@ -239,59 +489,349 @@ pub fn event_worker() -> impl Sipper<Never, WorkerEvent> {
     })
 }
 
-async fn run_command(item_name: &str, mut command: Command, mut output: SSender<WorkerEvent>) {
-    match command.spawn() {
+/// The command an install runs, led by the variables it runs with
+#[must_use]
+pub fn install_command(item: &LocalCrate, defaults: &InstallDefaults) -> Vec<String> {
+    let settings = item.build.merged(&defaults.build);
+
+    // Variables lead the command the way a shell takes them, the build settings win over the
+    // ones Crane sets
+    let mut full_command = runtime_env(defaults)
+        .into_iter()
+        .filter(|(key, _)| !settings.env.contains_key(*key))
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<String>>();
+
+    full_command.extend(settings.env_words());
+    full_command.push(String::from("cargo"));
+
+    if let Some(toolchain) = &settings.toolchain {
+        full_command.push(format!("+{toolchain}"));
+    }
+
+    full_command.push(String::from("install"));
+
+    if let Some(git) = &item.git_link {
+        full_command.push(String::from("--git"));
+        full_command.push(git.clone());
+
+        // A rebuild stays on the installed commit instead of following the reference
+        match &item.local_hash {
+            Some(hash) if item.rebuild => {
+                full_command.push(String::from("--rev"));
+                full_command.push(hash.clone());
+            }
+            _ => full_command.extend(item.git_ref.install_flags()),
+        }
+    }
+
+    full_command.push(item.name.clone());
+
+    if item.no_default_features {
+        full_command.push(String::from("--no-default-features"));
+    }
+
+    if !item.all_features {
+        for feature in &item.activated_features {
+            full_command.push(String::from("--features"));
+            full_command.push(feature.clone());
+        }
+    }
+
+    if item.uses_locked(defaults) {
+        full_command.push(String::from("--locked"));
+    }
+
+    if item.force {
+        full_command.push(String::from("--force"));
+    }
+
+    if let Some(jobs) = defaults.jobs {
+        full_command.push(String::from("--jobs"));
+        full_command.push(jobs.to_string());
+    }
+
+    full_command.extend(item.preserved_flags());
+    full_command.extend(settings.extra_args);
+
+    full_command
+}
+
+/// The variables every install runs with on top of its build settings
+#[must_use]
+pub fn runtime_env(defaults: &InstallDefaults) -> Vec<(&'static str, String)> {
+    // Draw the progress bar even though stderr is not a terminal
+    let mut env = vec![
+        ("CARGO_TERM_PROGRESS_WHEN", String::from("always")),
+        ("CARGO_TERM_PROGRESS_WIDTH", String::from("120")),
+    ];
+
+    if defaults.shared_target
+        && let Some(target_dir) = shared_target_dir()
+    {
+        env.push((
+            "CARGO_TARGET_DIR",
+            target_dir.to_string_lossy().into_owned(),
+        ));
+
+        // Leave a wrapper that is already configured alone
+        if !rustc_wrapper_configured()
+            && let Some(sccache) = sccache_path()
+        {
+            env.push(("RUSTC_WRAPPER", sccache.to_string_lossy().into_owned()));
+        }
+    }
+
+    env
+}
+
+/// The command that removes an installed crate
+#[must_use]
+pub fn uninstall_command(crate_name: &str) -> Vec<String> {
+    vec![
+        String::from("cargo"),
+        String::from("uninstall"),
+        crate_name.to_string(),
+    ]
+}
+
+/// Install or update one crate, sending its log and result to the UI
+async fn install_crate(
+    item: &LocalCrate,
+    defaults: &InstallDefaults,
+    cancel_state: &CancelState,
+    mut output: SSender<WorkerEvent>,
+) -> CommandResult {
+    let full_command = item
+        .command
+        .clone()
+        .unwrap_or_else(|| install_command(item, defaults));
+    let mut log = Vec::new();
+
+    let started = unix_now();
+    let timer = Instant::now();
+    let from = installed_build(&item.name);
+
+    output.send(WorkerEvent::Updating(item.name.clone())).await;
+
+    match take_snapshot(&item.name, defaults.snapshots) {
+        Ok(Some(snapshot)) => {
+            let line = format!("Saved {} for rollback", snapshot.label());
+            crate_log(&mut output, &mut log, &item.name, line).await;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!("Failed to snapshot {}: {e}", item.name);
+
+            let line = format!("Failed to save a rollback snapshot: {e}");
+            crate_log(&mut output, &mut log, &item.name, line).await;
+        }
+    }
+
+    let line = format!("Executing: {}", join_words(&mask_secrets(&full_command)));
+    crate_log(&mut output, &mut log, &item.name, line).await;
+
+    let (variables, args) = split_env(&full_command);
+
+    // The variables Crane needs are part of the command, so what was reviewed is what runs
+    let mut command = Command::new(&args[0]);
+    command
+        .args(&args[1..])
+        .envs(variables)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let (result, command_log) = run_command(
+        &item.name,
+        OperationType::Update,
+        command,
+        cancel_state,
+        output.clone(),
+    )
+    .await;
+
+    log.extend(command_log);
+
+    let mut features = item
+        .activated_features
+        .iter()
+        .cloned()
+        .collect::<Vec<String>>();
+    features.sort();
+
+    record_history(
+        &mut output,
+        HistoryEntry {
+            crate_name: item.name.clone(),
+            action: HistoryAction::Install,
+            from,
+            to: installed_build(&item.name),
+            features,
+            command: mask_secrets(&full_command),
+            started,
+            duration_ms: elapsed_ms(timer),
+            result: result.summary(),
+            success: result.is_success(),
+            log,
+        },
+    )
+    .await;
+
+    output
+        .send(WorkerEvent::CrateResult {
+            crate_name: item.name.clone(),
+            operation: OperationType::Update,
+            result: result.clone(),
+        })
+        .await;
+
+    result
+}
+
+/// Send a line of an operation to the UI and keep it for the history
+async fn crate_log(
+    output: &mut SSender<WorkerEvent>,
+    log: &mut Vec<String>,
+    crate_name: &str,
+    line: String,
+) {
+    log.push(line.clone());
+    output
+        .send(WorkerEvent::CrateLog(crate_name.to_string(), line))
+        .await;
+}
+
+/// Save a finished operation and hand it to the UI
+async fn record_history(output: &mut SSender<WorkerEvent>, entry: HistoryEntry) {
+    append_history(&entry);
+    output
+        .send(WorkerEvent::HistoryRecorded(Box::new(entry)))
+        .await;
+}
+
+fn elapsed_ms(timer: Instant) -> u64 {
+    u64::try_from(timer.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Run cargo while streaming its output, returning how it ended and every line it printed
+async fn run_command(
+    item_name: &str,
+    operation: OperationType,
+    mut command: Command,
+    cancel_state: &CancelState,
+    mut output: SSender<WorkerEvent>,
+) -> (CommandResult, Vec<String>) {
+    // Put cargo in its own process group so cancelling also stops the rustc processes it
+    // started
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let action = match operation {
+        OperationType::Update => "installing",
+        OperationType::Delete => "uninstalling",
+        OperationType::Rollback => "rolling back",
+    };
+
+    // Lines from both pipes in the order they arrived
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let mut log = Vec::new();
+
+    let result = match command.spawn() {
         Ok(mut child) => {
+            let pid = child.id();
+            cancel_state.add_child(pid);
+
             let stdout = child.stdout.take().unwrap();
             let stderr = child.stderr.take().unwrap();
 
             let mut stdout_lines = BufReader::new(stdout).lines();
-            let mut stderr_lines = BufReader::new(stderr).lines();
+            // The progress bar is redrawn with carriage returns instead of new lines
+            let mut stderr_chunks = BufReader::new(stderr).split(b'\r');
 
             let mut output_clone = output.clone();
+            let crate_name = item_name.to_string();
+            let lines_clone = lines.clone();
 
             let stdout_task = tokio::spawn(async move {
                 while let Ok(Some(line)) = stdout_lines.next_line().await {
-                    output_clone.send(WorkerEvent::Log(line)).await;
+                    lines_clone.lock().unwrap().push(line.clone());
+                    output_clone
+                        .send(WorkerEvent::CrateLog(crate_name.clone(), line))
+                        .await;
                 }
             });
 
             let mut output_clone = output.clone();
+            let crate_name = item_name.to_string();
+            let lines_clone = lines.clone();
 
             let stderr_task = tokio::spawn(async move {
-                while let Ok(Some(line)) = stderr_lines.next_line().await {
-                    output_clone.send(WorkerEvent::Log(line)).await;
+                let mut progress = BuildProgress::default();
+
+                while let Ok(Some(chunk)) = stderr_chunks.next_segment().await {
+                    for line in String::from_utf8_lossy(&chunk).lines() {
+                        let previous = progress.clone();
+
+                        // Redrawn bars would flood the log
+                        if progress.update(line) != BuildLine::Bar {
+                            lines_clone.lock().unwrap().push(line.to_string());
+                            output_clone
+                                .send(WorkerEvent::CrateLog(crate_name.clone(), line.to_string()))
+                                .await;
+                        }
+
+                        if progress != previous {
+                            output_clone
+                                .send(WorkerEvent::BuildProgress {
+                                    crate_name: crate_name.clone(),
+                                    progress: progress.clone(),
+                                })
+                                .await;
+                        }
+                    }
                 }
             });
 
             // Wait for cargo to finish
             let status = child.wait().await;
 
+            cancel_state.remove_child(pid);
+
             // Wait for both log tasks to finish
             let _ = join!(stdout_task, stderr_task);
 
+            log.append(&mut lines.lock().unwrap());
+
             match status {
                 Ok(status) => {
-                    let msg = format!("Finished installing {item_name} with status: {status}");
-                    output.send(WorkerEvent::Log(msg)).await;
+                    let msg = format!("Finished {action} {item_name} with status: {status}");
+                    crate_log(&mut output, &mut log, item_name, msg).await;
+
+                    if status.success() {
+                        CommandResult::Success
+                    } else if cancel_state.is_cancelled() {
+                        CommandResult::Cancelled
+                    } else {
+                        CommandResult::Failed(status.code())
+                    }
                 }
                 Err(e) => {
-                    output
-                        .send(WorkerEvent::Log(format!(
-                            "Failed to wait on cargo for {item_name}: {e}"
-                        )))
-                        .await;
+                    let msg = format!("Failed to wait on cargo for {item_name}: {e}");
+                    crate_log(&mut output, &mut log, item_name, msg).await;
+
+                    CommandResult::Failed(None)
                 }
             }
         }
         Err(e) => {
-            output
-                .send(WorkerEvent::Log(format!(
-                    "Failed to spawn cargo install for {item_name}: {e}"
-                )))
-                .await;
+            let msg = format!("Failed to spawn cargo for {action} {item_name}: {e}");
+            crate_log(&mut output, &mut log, item_name, msg).await;
+
+            CommandResult::SpawnFailed(e.to_string())
         }
-    }
+    };
+
+    (result, log)
 }
 
 #[must_use]
@@ -303,31 +843,268 @@ pub fn parse_github_body(body: &str) -> String {
     format!("\n{}\n", &body[..end_point].trim())
 }
 
+/// How a single cargo install or uninstall ended
+#[derive(Debug, Clone)]
+pub enum CommandResult {
+    Success,
+    /// Cargo exited with a non-zero status. The code is missing when it was killed by a signal
+    Failed(Option<i32>),
+    SpawnFailed(String),
+    /// Stopped from the UI while running
+    Cancelled,
+}
+
+impl CommandResult {
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        matches!(self, CommandResult::Success)
+    }
+
+    #[must_use]
+    pub fn summary(&self) -> String {
+        match self {
+            CommandResult::Success => String::from("Succeeded"),
+            CommandResult::Failed(Some(code)) => format!("Failed (exit code {code})"),
+            CommandResult::Failed(None) => String::from("Failed (terminated)"),
+            CommandResult::SpawnFailed(_) => String::from("Failed to start cargo"),
+            CommandResult::Cancelled => String::from("Cancelled"),
+        }
+    }
+}
+
+/// Tracks the running cargo processes so an operation can be stopped from the UI
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    children: Mutex<HashSet<u32>>,
+}
+
+impl CancelState {
+    fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn add_child(&self, pid: Option<u32>) {
+        let Some(pid) = pid else {
+            return;
+        };
+
+        let mut children = self.children.lock().unwrap();
+        children.insert(pid);
+
+        // A stop that came in while cargo was starting has already gone through the list, so
+        // checking under the lock means every child is stopped by one side or the other
+        let cancelled = self.is_cancelled();
+        drop(children);
+
+        if cancelled {
+            stop_process(pid);
+        }
+    }
+
+    fn remove_child(&self, pid: Option<u32>) {
+        if let Some(pid) = pid {
+            self.children.lock().unwrap().remove(&pid);
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+
+        let children = self.children.lock().unwrap().clone();
+
+        for pid in children {
+            stop_process(pid);
+        }
+    }
+}
+
+/// Stop a cargo process along with the rustc processes it started
+fn stop_process(pid: u32) {
+    info!("Stopping cargo process {pid}");
+
+    // The negative pid targets the whole process group
+    #[cfg(unix)]
+    let result = std::process::Command::new("kill")
+        .args(["-TERM", "--", &format!("-{pid}")])
+        .status();
+
+    #[cfg(windows)]
+    let result = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .status();
+
+    if let Err(e) = result {
+        error!("Failed to stop cargo process {pid}: {e}");
+    }
+}
+
+/// Where each crate of a batch ended up after it was cancelled
+#[derive(Debug, Clone, Default)]
+pub struct CancelReport {
+    pub finished: Vec<String>,
+    pub aborted: Vec<String>,
+    pub not_started: Vec<String>,
+}
+
+impl CancelReport {
+    fn add(&mut self, crate_name: &str, result: &CommandResult) {
+        if let CommandResult::Cancelled = result {
+            self.aborted.push(crate_name.to_string());
+        } else {
+            self.finished.push(crate_name.to_string());
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum WorkerEvent {
     Ready(Sender<WorkerInput>),
     ReadyFailed,
     SuccessCrate(Box<CrateResponse>),
+    SuccessIndex {
+        crate_name: String,
+        versions: Vec<IndexVersion>,
+    },
     SuccessGitCommit {
         crate_name: String,
         commit: String,
         load_bar: bool,
     },
-    ErrorCrate(usize),
-    Updating((String, usize)),
-    Deleting((String, usize)),
+    ErrorCrate(String),
+    ErrorGitCommit {
+        crate_name: String,
+        load_bar: bool,
+    },
+    Updating(String),
+    Deleting(String),
     DoneCrateCheck,
     DoneUpdate,
     DoneDelete,
-    Log(String),
+    CrateLog(String, String),
+    CrateResult {
+        crate_name: String,
+        operation: OperationType,
+        result: CommandResult,
+    },
+    Cancelled(CancelReport),
+    HistoryRecorded(Box<HistoryEntry>),
+    DoneRollback {
+        crate_name: String,
+        result: Result<(), String>,
+    },
     NewUpdateAvailable(String),
+    BuildProgress {
+        crate_name: String,
+        progress: BuildProgress,
+    },
+    /// The rustc of a toolchain from the build settings, or the default one when None
+    RustcVersion {
+        toolchain: Option<String>,
+        result: Result<Rustc, String>,
+    },
+    /// The size of the shared target directory and whether sccache is on the PATH
+    BuildCache {
+        size: u64,
+        sccache: bool,
+    },
+    /// How many bytes were freed
+    BuildCacheCleaned(Result<u64, String>),
+    /// Advisories by crate name, crates without any are left out
+    AdvisoryScan(Result<HashMap<String, Vec<Advisory>>, String>),
+    SearchResults {
+        query: String,
+        result: Result<Vec<SearchResult>, String>,
+    },
+    CrateDetails {
+        crate_name: String,
+        result: Result<CrateDetails, String>,
+    },
+    /// The binaries a release of a crate ships
+    ReleaseBins {
+        crate_name: String,
+        version: Version,
+        bins: Vec<String>,
+    },
 }
 
 pub enum WorkerInput {
-    GetCrateVersion(Vec<String>, u64),
+    GetCrateVersion {
+        crates: Vec<String>,
+        index_url: String,
+        rate_limit: u64,
+    },
     // Whether the bool value should increase load bar on the ui
-    GetGitCommit(HashMap<String, String>, bool),
-    UpdateCrates(Vec<LocalCrate>),
-    DeleteCrates(Vec<String>),
+    GetGitCommit(HashMap<String, (String, GitReference)>, bool),
+    UpdateCrates(Vec<LocalCrate>, InstallDefaults),
+    DeleteCrates(Vec<LocalCrate>),
+    Rollback(Snapshot),
+    ScanAdvisories(PathBuf, Vec<ScanTarget>),
+    SearchCrates {
+        query: String,
+        rate_limit: u64,
+    },
+    GetCrateDetails {
+        name: String,
+        index_url: String,
+    },
+    GetReleaseBins {
+        releases: Vec<(String, Version)>,
+        rate_limit: u64,
+    },
+    GetRustcVersion(Option<String>),
+    GetBuildCache,
+    CleanBuildCache,
     CheckLatestVersion,
+    CancelOperation,
+}
+
+/// The index and the crates.io rate limit from the config
+fn registry_settings(config: Option<&Config>) -> (String, u64) {
+    config.map_or_else(
+        || (DEFAULT_INDEX_URL.to_string(), 1000),
+        |c| (c.index_url.clone(), c.crate_rate_limit_ms),
+    )
+}
+
+impl WorkerInput {
+    /// A version check of the given crates using the index and rate limit from the config
+    #[must_use]
+    pub fn crate_version(crates: Vec<String>, config: Option<&Config>) -> Self {
+        let (index_url, rate_limit) = registry_settings(config);
+
+        WorkerInput::GetCrateVersion {
+            crates,
+            index_url,
+            rate_limit,
+        }
+    }
+
+    #[must_use]
+    pub fn search(query: String, config: Option<&Config>) -> Self {
+        let (_, rate_limit) = registry_settings(config);
+
+        WorkerInput::SearchCrates { query, rate_limit }
+    }
+
+    #[must_use]
+    pub fn crate_details(name: String, config: Option<&Config>) -> Self {
+        let (index_url, _) = registry_settings(config);
+
+        WorkerInput::GetCrateDetails { name, index_url }
+    }
+
+    #[must_use]
+    pub fn release_bins(releases: Vec<(String, Version)>, config: Option<&Config>) -> Self {
+        let (_, rate_limit) = registry_settings(config);
+
+        WorkerInput::GetReleaseBins {
+            releases,
+            rate_limit,
+        }
+    }
 }